@@ -1,39 +1,103 @@
 use anyhow::Context;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, path::PathBuf, sync::OnceLock};
+use std::{
+    collections::HashMap,
+    env, fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
-fn obsidian_app_settings_path() -> &'static PathBuf {
-    static SETTINGS_PATH: OnceLock<PathBuf> = OnceLock::new();
+/// The places Obsidian keeps `obsidian.json`, most likely first
+fn candidate_settings_paths() -> Vec<PathBuf> {
+    if let Ok(path) = env::var("OBX_OBSIDIAN_SETTINGS") {
+        return vec![PathBuf::from(path)];
+    }
 
-    SETTINGS_PATH.get_or_init(|| {
-        #[cfg(target_os = "macos")]
-        let path = etcetera::home_dir()
-            .expect("should be able to find home dir")
-            .join("Library/Application Support/obsidian/obsidian.json");
+    #[cfg(target_os = "macos")]
+    let paths = vec![etcetera::home_dir()
+        .expect("should be able to find home dir")
+        .join("Library/Application Support/obsidian/obsidian.json")];
 
-        #[cfg(target_os = "windows")]
-        let path = PathBuf::from("%APPDATA%\\Obsidian\\obsidian.json");
+    #[cfg(target_os = "windows")]
+    let paths = vec![PathBuf::from(env::var("APPDATA").unwrap_or_default())
+        .join("Obsidian")
+        .join("obsidian.json")];
 
-        #[cfg(target_os = "linux")]
-        let path = etcetera::choose_base_strategy()
+    #[cfg(target_os = "linux")]
+    let paths = {
+        use etcetera::BaseStrategy;
+
+        let home = etcetera::home_dir().expect("should be able to find home dir");
+        let config_dir = etcetera::choose_base_strategy()
             .expect("etcetera base strategy should work")
-            .config_dir()
-            .join("Obsidian/obsidian.json");
+            .config_dir();
+
+        vec![
+            config_dir.join("obsidian/obsidian.json"),
+            config_dir.join("Obsidian/obsidian.json"),
+            // Flatpak and Snap installs keep their config inside the sandbox
+            home.join(".var/app/md.obsidian.Obsidian/config/obsidian/obsidian.json"),
+            home.join("snap/obsidian/current/.config/obsidian/obsidian.json"),
+        ]
+    };
 
-        path
+    paths
+}
+
+/// The first `obsidian.json` that exists, or the most likely location if none do
+pub fn obsidian_app_settings_path() -> &'static PathBuf {
+    static SETTINGS_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+    SETTINGS_PATH.get_or_init(|| {
+        let candidates = candidate_settings_paths();
+
+        candidates
+            .iter()
+            .find(|path| path.is_file())
+            .unwrap_or(&candidates[0])
+            .clone()
     })
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Vault {
     pub path: PathBuf,
-    pub ts: PathBuf,
+    /// When the vault was last opened, in milliseconds since the epoch
+    #[serde(default)]
+    pub ts: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub open: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Settings {
     pub vaults: HashMap<String, Vault>,
+
+    /// Everything else in the file, kept so it's written back unchanged
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Settings {
+    /// The registered vaults, most recently opened first
+    pub fn recent_vaults(&self) -> Vec<&Vault> {
+        let mut vaults: Vec<&Vault> = self.vaults.values().collect();
+        vaults.sort_by(|a, b| b.ts.cmp(&a.ts).then_with(|| a.path.cmp(&b.path)));
+        vaults
+    }
+
+    /// The vault that's open in Obsidian, or the one opened most recently
+    pub fn open_vault(&self) -> Option<&Vault> {
+        let recent = self.recent_vaults();
+
+        recent
+            .iter()
+            .find(|vault| vault.open == Some(true))
+            .or(recent.first())
+            .copied()
+    }
 }
 
 pub fn read() -> anyhow::Result<Settings> {
@@ -45,7 +109,132 @@ pub fn read() -> anyhow::Result<Settings> {
         )
     })?;
 
-    let settings = serde_yaml::from_str(&settings_file)?;
+    let settings = serde_json::from_str(&settings_file).with_context(|| {
+        format!(
+            "failed to parse obsidian app settings file at path `{}`",
+            settings_path.display()
+        )
+    })?;
 
     Ok(settings)
 }
+
+pub fn write(settings: &Settings) -> anyhow::Result<()> {
+    let settings_path = obsidian_app_settings_path();
+
+    if let Some(settings_dir) = settings_path.parent() {
+        fs::create_dir_all(settings_dir)?;
+    }
+
+    fs::write(settings_path, serde_json::to_string(settings)?).with_context(|| {
+        format!(
+            "failed to write obsidian app settings file at path `{}`",
+            settings_path.display()
+        )
+    })
+}
+
+/// A 16 character hex id like the ones Obsidian gives vaults
+fn generate_vault_id(path: &Path, ts: u64) -> String {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    ts.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Add a vault to `obsidian.json` so it shows in the app's vault switcher. Returns `false`
+/// if it was already registered
+pub fn register(vault_path: &Path) -> anyhow::Result<bool> {
+    // Obsidian may never have been opened, in which case there's nothing to read yet
+    let mut settings = if obsidian_app_settings_path().is_file() {
+        read()?
+    } else {
+        Settings::default()
+    };
+
+    if settings
+        .vaults
+        .values()
+        .any(|vault| vault.path == vault_path)
+    {
+        return Ok(false);
+    }
+
+    let ts = u64::try_from(Utc::now().timestamp_millis()).unwrap_or_default();
+    settings.vaults.insert(
+        generate_vault_id(vault_path, ts),
+        Vault {
+            path: vault_path.to_path_buf(),
+            ts,
+            open: None,
+        },
+    );
+
+    write(&settings)?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Settings {
+        serde_json::from_str(
+            r#"{"vaults": {
+                "a1": {"path": "/vaults/old", "ts": 1000},
+                "b2": {"path": "/vaults/recent", "ts": 3000},
+                "c3": {"path": "/vaults/open", "ts": 2000, "open": true}
+            }}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn recent_vaults_are_ordered_by_ts() {
+        let settings = example();
+        let paths: Vec<&Path> = settings
+            .recent_vaults()
+            .iter()
+            .map(|vault| vault.path.as_path())
+            .collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("/vaults/recent"),
+                Path::new("/vaults/open"),
+                Path::new("/vaults/old")
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_settings() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"vaults": {}, "updateDisabled": true}"#).unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&settings).unwrap(),
+            r#"{"vaults":{},"updateDisabled":true}"#
+        );
+    }
+
+    #[test]
+    fn generated_ids_look_like_obsidians() {
+        let id = generate_vault_id(Path::new("/vaults/new"), 1000);
+
+        assert_eq!(id.len(), 16);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn open_vault_prefers_the_open_flag() {
+        let settings = example();
+
+        assert_eq!(
+            settings.open_vault().map(|vault| vault.path.as_path()),
+            Some(Path::new("/vaults/open"))
+        );
+    }
+}
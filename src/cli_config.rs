@@ -1,106 +1,721 @@
-use crate::app_settings;
+use crate::{app_settings, error::ObxError, output::Format};
 use anyhow::{bail, Context};
+use clap::ValueEnum;
 use etcetera::BaseStrategy;
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use serde_yaml::{Mapping, Value};
 use std::{
-    env::{self, VarError},
-    fs,
+    env, fs,
     path::{Path, PathBuf},
-    sync::OnceLock,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
 };
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Vault {
     pub name: String,
     pub path: PathBuf,
+
+    /// Overrides for the global defaults, for this vault only
+    #[serde(flatten)]
+    pub settings: VaultSettings,
+}
+
+/// Settings that can be set once for every vault, and overridden per vault
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct VaultSettings {
+    /// Folder new notes are created in when no folder is given, relative to the vault
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes_folder: Option<PathBuf>,
+
+    /// Folder templates are kept in, relative to the vault
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub templates_folder: Option<PathBuf>,
+
+    /// The editor to open notes in, used instead of `$EDITOR` when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+
+    /// The format commands print in when `--format` isn't given, e.g. `json`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_output_format"
+    )]
+    pub output_format: Option<String>,
+
+    /// Glob patterns for files and folders obx should skip, e.g. `templates/` or `*.excalidraw.md`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+
+    /// Maintain `created`/`updated` properties on notes, on with the default settings
+    /// unless this is set. `false` opts out, for every vault or just one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamps: Option<Timestamps>,
+}
+
+/// Accept only the formats every command can print in, so not the `graph export` formats
+fn deserialize_output_format<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    let output_format = Option::<String>::deserialize(deserializer)?;
+
+    if let Some(name) = &output_format {
+        if !Format::from_str(name, true).is_ok_and(|format| !format.is_graph_only()) {
+            let expected: Vec<String> = Format::value_variants()
+                .iter()
+                .filter(|format| !format.is_graph_only())
+                .filter_map(|format| format.to_possible_value())
+                .map(|value| value.get_name().to_string())
+                .collect();
+
+            return Err(D::Error::custom(format!(
+                "`{name}` is not an output format, expected one of {}",
+                expected.join(", ")
+            )));
+        }
+    }
+
+    Ok(output_format)
+}
+
+/// The `timestamps` setting, either its full settings or just whether it's on
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Timestamps {
+    Enabled(bool),
+    Settings(TimestampSettings),
+}
+
+impl VaultSettings {
+    /// These settings with anything unset taken from `defaults`. Ignore patterns are
+    /// combined rather than replaced
+    pub fn merged_with(&self, defaults: &VaultSettings) -> VaultSettings {
+        let mut ignore = defaults.ignore.clone();
+        for pattern in &self.ignore {
+            if !ignore.contains(pattern) {
+                ignore.push(pattern.clone());
+            }
+        }
+
+        VaultSettings {
+            notes_folder: self.notes_folder.clone().or(defaults.notes_folder.clone()),
+            templates_folder: self
+                .templates_folder
+                .clone()
+                .or(defaults.templates_folder.clone()),
+            editor: self.editor.clone().or(defaults.editor.clone()),
+            output_format: self
+                .output_format
+                .clone()
+                .or(defaults.output_format.clone()),
+            ignore,
+            timestamps: match (&self.timestamps, &defaults.timestamps) {
+                (None, timestamps) => timestamps.clone(),
+                // `true` turns them back on, with the settings from the defaults
+                (Some(Timestamps::Enabled(true)), Some(Timestamps::Settings(settings))) => {
+                    Some(Timestamps::Settings(TimestampSettings {
+                        enabled: true,
+                        ..settings.clone()
+                    }))
+                }
+                (timestamps, _) => timestamps.clone(),
+            },
+        }
+    }
+
+    /// The timestamp settings to use, or `None` if timestamps have been turned off
+    pub fn timestamp_settings(&self) -> Option<TimestampSettings> {
+        match &self.timestamps {
+            None | Some(Timestamps::Enabled(true)) => Some(TimestampSettings::default()),
+            Some(Timestamps::Enabled(false)) => None,
+            Some(Timestamps::Settings(settings)) => settings.enabled.then(|| settings.clone()),
+        }
+    }
+}
+
+/// The file in the root of a vault that can override its settings
+pub const LOCAL_CONFIG_FILE: &str = ".obx.yml";
+
+/// Read the `.obx.yml` in the root of a vault, if there is one. Its settings take
+/// precedence over the vault's entry in the config
+pub fn read_local(vault_path: &Path) -> anyhow::Result<Option<VaultSettings>> {
+    let local_path = vault_path.join(LOCAL_CONFIG_FILE);
+    if !local_path.is_file() {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(&local_path)
+        .with_context(|| format!("Could not read {}", local_path.display()))?;
+
+    // An empty file is valid and changes nothing
+    if contents.trim().is_empty() {
+        return Ok(Some(VaultSettings::default()));
+    }
+
+    let local = serde_yaml::from_str(&contents).with_context(|| {
+        ObxError::Parse(format!(
+            "Invalid vault settings in {}",
+            local_path.display()
+        ))
+    })?;
+
+    Ok(Some(local))
+}
+
+/// Frontmatter properties that obx keeps up to date when it creates or changes a note
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TimestampSettings {
+    pub enabled: bool,
+    pub created_key: String,
+    pub updated_key: String,
+    /// A chrono strftime format, e.g. `%Y-%m-%d`
+    pub format: String,
+    /// `local`, `utc` or a fixed offset such as `+02:00`
+    pub timezone: String,
+}
+
+impl Default for TimestampSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            created_key: "created".to_string(),
+            updated_key: "updated".to_string(),
+            format: "%Y-%m-%dT%H:%M:%S".to_string(),
+            timezone: "local".to_string(),
+        }
+    }
+}
+
+/// The version of the config format this build of obx reads and writes
+pub const CONFIG_VERSION: u32 = 1;
+
+fn current_version() -> u32 {
+    CONFIG_VERSION
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Config {
+    /// The version of the config format, older files are migrated when they're read
+    #[serde(default = "current_version")]
+    pub version: u32,
+
     pub current_vault: String,
     pub vaults: Vec<Vault>,
+
+    /// Defaults for every vault, which each vault can override
+    #[serde(flatten)]
+    pub defaults: VaultSettings,
+
+    /// How to open an editor at a line when it isn't one obx already knows, e.g.
+    /// `+{line} {file}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor_line_pattern: Option<String>,
 }
 
 fn get_config_dir() -> &'static PathBuf {
     static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
 
-    CONFIG_DIR.get_or_init(|| match env::var("OBX_CONFIG_DIR") {
-        Ok(dir) => PathBuf::from(dir),
-        Err(VarError::NotPresent) => {
+    // Paths don't have to be valid unicode, so OBX_CONFIG_DIR is used as it is
+    CONFIG_DIR.get_or_init(|| match env::var_os("OBX_CONFIG_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
             let strategy =
                 etcetera::choose_base_strategy().expect("etcetera base strategy should work");
             strategy.config_dir().join("obx")
         }
-        _ => panic!("Malformed OBX_CONFIG_DIR"),
     })
 }
 
+/// Overrides from the global command line flags, set once at startup
+static CONFIG_FILE_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+static VAULT_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Read and write the config at `path` instead of the default location, from `--config`
+pub fn set_config_file(path: PathBuf) {
+    let _ = CONFIG_FILE_OVERRIDE.set(path);
+}
+
+/// Use the vault named `name` instead of the current vault, from `--vault`
+pub fn set_vault_override(name: String) {
+    let _ = VAULT_OVERRIDE.set(name);
+}
+
+pub fn vault_override() -> Option<String> {
+    VAULT_OVERRIDE.get().cloned()
+}
+
 pub fn get_config_path() -> PathBuf {
+    if let Some(path) = CONFIG_FILE_OVERRIDE.get() {
+        return path.clone();
+    }
+
     let config_dir = get_config_dir();
     config_dir.join("config.yml")
 }
 
-fn get_config() -> anyhow::Result<config::Config> {
+/// `OBX_` variables that configure obx itself rather than setting a config key
+const RESERVED_ENV_VARS: [&str; 3] = ["CONFIG_DIR", "CONFIG", "OBSIDIAN_SETTINGS"];
+
+/// Config values from `OBX_` environment variables, with `__` between nested keys and
+/// numbers for list items, e.g. `OBX_CURRENT_VAULT` or `OBX_VAULTS__0__PATH`
+#[derive(Debug, Clone)]
+struct EnvOverrides;
+
+/// Turn the part of a variable name after `OBX_` into a config path such as `vaults[0].path`
+fn env_key_path(key: &str) -> Option<String> {
+    let mut path = String::new();
+
+    for segment in key.to_lowercase().split("__") {
+        if segment.is_empty() {
+            return None;
+        }
+
+        if segment.chars().all(|c| c.is_ascii_digit()) {
+            path.push_str(&format!("[{segment}]"));
+        } else {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(segment);
+        }
+    }
+
+    (!path.is_empty() && !path.starts_with('[')).then_some(path)
+}
+
+impl config::Source for EnvOverrides {
+    fn clone_into_box(&self) -> Box<dyn config::Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<config::Map<String, config::Value>, config::ConfigError> {
+        let origin = "the environment".to_string();
+
+        let values = env::vars()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix("OBX_")?;
+                if RESERVED_ENV_VARS.contains(&key) {
+                    return None;
+                }
+
+                // Booleans are parsed here because flattened settings can't convert
+                // strings themselves
+                let kind = match value.as_str() {
+                    "true" => config::ValueKind::Boolean(true),
+                    "false" => config::ValueKind::Boolean(false),
+                    _ => config::ValueKind::String(value),
+                };
+
+                Some((env_key_path(key)?, config::Value::new(Some(&origin), kind)))
+            })
+            .collect();
+
+        Ok(values)
+    }
+}
+
+/// A change to the config format, upgrading a config from the version before it
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&mut Mapping),
+}
+
+/// Every change to the config format, oldest first
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    description: "Add a `version` key",
+    // Configs from before versioning are otherwise unchanged
+    apply: |_| {},
+}];
+
+/// The version of a config as it's saved, configs from before versioning are version 0
+fn saved_version(config: &Mapping) -> u32 {
+    config
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |version| version as u32)
+}
+
+/// Set the version as the first key, so it's the first thing seen in the file
+fn set_version(config: &mut Mapping, version: u32) {
+    config.shift_remove("version");
+
+    let mut versioned = Mapping::new();
+    versioned.insert(Value::from("version"), Value::from(version));
+    versioned.extend(std::mem::take(config));
+
+    *config = versioned;
+}
+
+/// Upgrade a config to the current version, returning the migrations that were applied
+pub fn migrate(config: &mut Mapping) -> Vec<&'static Migration> {
+    let from = saved_version(config);
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > from).collect();
+
+    for migration in &pending {
+        (migration.apply)(config);
+        set_version(config, migration.version);
+    }
+
+    pending
+}
+
+/// The changes needed to bring the config file up to date
+pub struct MigrationPlan {
+    pub from: u32,
+    pub migrations: Vec<&'static Migration>,
+    /// The migrated config file
+    pub migrated: String,
+}
+
+/// Work out how the config file would be migrated, without changing it. Returns `None`
+/// when there's no config file or it's already up to date
+pub fn plan_migration() -> anyhow::Result<Option<MigrationPlan>> {
     let config_path = get_config_path();
+    if !config_path.is_file() {
+        return Ok(None);
+    }
 
-    let settings = config::Config::builder()
-        .add_source(config::File::from(config_path))
-        .build()?;
+    let contents = fs::read_to_string(&config_path)
+        .with_context(|| format!("Could not read config file {}", config_path.display()))?;
 
-    Ok(settings)
+    let mut config = match serde_yaml::from_str(&contents).with_context(|| {
+        ObxError::Parse(format!("Invalid config file {}", config_path.display()))
+    })? {
+        Value::Mapping(mapping) => mapping,
+        Value::Null => return Ok(None),
+        _ => bail!(ObxError::Parse(format!(
+            "Invalid config file {}",
+            config_path.display()
+        ))),
+    };
+
+    let from = saved_version(&config);
+    let migrations = migrate(&mut config);
+    if migrations.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(MigrationPlan {
+        from,
+        migrations,
+        migrated: migrated_text(&contents, &config)?,
+    }))
 }
 
+/// The text of the migrated config. When setting the version in the original text gives
+/// the same config that's used, so comments and formatting are kept
+fn migrated_text(contents: &str, migrated: &Mapping) -> anyhow::Result<String> {
+    let text = with_version_line(contents, saved_version(migrated));
+
+    match serde_yaml::from_str::<Mapping>(&text) {
+        Ok(config) if &config == migrated => Ok(text),
+        _ => Ok(serde_yaml::to_string(migrated)?),
+    }
+}
+
+/// Replace the top level `version` line, or add one at the start of the document
+fn with_version_line(contents: &str, version: u32) -> String {
+    let version_line = format!("version: {version}");
+    let mut lines: Vec<&str> = contents.lines().collect();
+
+    match lines.iter().position(|line| line.starts_with("version:")) {
+        Some(idx) => lines[idx] = &version_line,
+        None => {
+            let start = usize::from(lines.first().is_some_and(|line| line.trim_end() == "---"));
+            lines.insert(start, &version_line);
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Where the config file is copied before it's migrated, e.g. `config.yml.bak`
+pub fn backup_path(config_path: &Path) -> PathBuf {
+    let mut backup = config_path.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+/// Migrate the config file in place if it's out of date, keeping a backup of the original
+pub fn migrate_file() -> anyhow::Result<Option<MigrationPlan>> {
+    let Some(plan) = plan_migration()? else {
+        return Ok(None);
+    };
+
+    save_migration(&plan)?;
+
+    Ok(Some(plan))
+}
+
+fn save_migration(plan: &MigrationPlan) -> anyhow::Result<()> {
+    let config_path = get_config_path();
+    let backup = backup_path(&config_path);
+
+    fs::copy(&config_path, &backup)
+        .with_context(|| format!("Could not back up config file to {}", backup.display()))?;
+    write_raw(&plan.migrated)
+}
+
+/// The migrated config, when the config file itself couldn't be migrated
+static MIGRATED_CONFIG: OnceLock<String> = OnceLock::new();
+
+/// Keys in `raw` that aren't part of the config and would be ignored
+fn unknown_keys(raw: &Value, known: &Value, prefix: &str, found: &mut Vec<String>) {
+    match (raw, known) {
+        (Value::Mapping(raw), Value::Mapping(known)) => {
+            for (key, value) in raw {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                let path = match prefix {
+                    "" => key.to_string(),
+                    _ => format!("{prefix}.{key}"),
+                };
+
+                match known.get(key) {
+                    Some(known_value) => unknown_keys(value, known_value, &path, found),
+                    // Empty values aren't written back out, so they can't be told apart
+                    // from unknown keys
+                    None if is_empty(value) => {}
+                    None => found.push(path),
+                }
+            }
+        }
+        (Value::Sequence(raw), Value::Sequence(known)) => {
+            for (idx, (value, known_value)) in raw.iter().zip(known).enumerate() {
+                unknown_keys(value, known_value, &format!("{prefix}.{idx}"), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Sequence(sequence) => sequence.is_empty(),
+        Value::Mapping(mapping) => mapping.is_empty(),
+        _ => false,
+    }
+}
+
+/// Warnings about parts of the config file obx will ignore
+fn config_warnings(contents: &str) -> Vec<String> {
+    let Ok(raw) = serde_yaml::from_str::<Value>(contents) else {
+        return vec![];
+    };
+
+    let mut warnings = vec![];
+
+    if let Value::Mapping(mapping) = &raw {
+        let version = saved_version(mapping);
+        if version > CONFIG_VERSION {
+            warnings.push(format!(
+                "Config is version {version} but this version of obx only understands up to version {CONFIG_VERSION}, some settings may be ignored"
+            ));
+        }
+    }
+
+    // Invalid configs are reported when they're read
+    let Some(known) = serde_yaml::from_value::<Config>(raw.clone())
+        .ok()
+        .and_then(|config| serde_yaml::to_value(config).ok())
+    else {
+        return warnings;
+    };
+
+    let mut unknown = vec![];
+    unknown_keys(&raw, &known, "", &mut unknown);
+    warnings.extend(
+        unknown
+            .into_iter()
+            .map(|key| format!("Ignoring unknown config key `{key}`")),
+    );
+
+    warnings
+}
+
+/// Migrate the config file and warn about anything in it that'll be ignored, once per run
+fn prepare_config_file() -> anyhow::Result<()> {
+    static PREPARED: AtomicBool = AtomicBool::new(false);
+    if PREPARED.swap(true, Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    if let Some(plan) = plan_migration()? {
+        let config_path = get_config_path();
+        match save_migration(&plan) {
+            Ok(()) => eprintln!(
+                "Migrated config from version {} to {CONFIG_VERSION}, the original was saved to {}",
+                plan.from,
+                backup_path(&config_path).display()
+            ),
+            // A read-only config, e.g. one shared with `--config`, still works but is
+            // migrated again on every run
+            Err(e) => {
+                eprintln!(
+                    "Warning: Could not migrate config file {}, using a migrated copy for now: {e:#}",
+                    config_path.display()
+                );
+                let _ = MIGRATED_CONFIG.set(plan.migrated);
+            }
+        }
+    }
+
+    let contents = match MIGRATED_CONFIG.get() {
+        Some(contents) => Ok(contents.clone()),
+        None => fs::read_to_string(get_config_path()),
+    };
+    if let Ok(contents) = contents {
+        for warning in config_warnings(&contents) {
+            eprintln!("Warning: {warning}");
+        }
+    }
+
+    Ok(())
+}
+
+fn get_config(with_env: bool) -> anyhow::Result<config::Config> {
+    prepare_config_file()?;
+
+    let config_path = get_config_path();
+
+    // The file is optional so obx can be configured entirely from the environment
+    let mut builder = match MIGRATED_CONFIG.get() {
+        Some(contents) => config::Config::builder()
+            .add_source(config::File::from_str(contents, config::FileFormat::Yaml)),
+        None => config::Config::builder().add_source(
+            config::File::new(&config_path.to_string_lossy(), config::FileFormat::Yaml)
+                .required(false),
+        ),
+    };
+
+    if with_env {
+        builder = builder.add_source(EnvOverrides);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// The config commands should use, with any `OBX_` environment overrides applied
 pub fn read() -> anyhow::Result<Config> {
-    let config = get_config()?
+    let config = get_config(true)?
         .try_deserialize::<Config>()
-        .context("failed to deserialize config")?;
+        .map_err(|e| ObxError::Parse(format!("Invalid config: {e}")))?;
     Ok(config)
 }
 
+/// The config as saved in the config file, for commands that change it and write it
+/// back, so environment overrides aren't saved
+pub fn read_persisted() -> anyhow::Result<Config> {
+    let config = get_config(false)?
+        .try_deserialize::<Config>()
+        .map_err(|e| ObxError::Parse(format!("Invalid config: {e}")))?;
+    Ok(config)
+}
+
+/// The config file as it's saved, for commands that change it. Unlike `read_persisted`
+/// this doesn't go through `Config`, so keys stay in the order they were written and any
+/// obx doesn't know about are kept
+pub fn read_raw() -> anyhow::Result<Value> {
+    prepare_config_file()?;
+
+    let config_path = get_config_path();
+    let contents = match MIGRATED_CONFIG.get() {
+        Some(contents) => contents.clone(),
+        None if !config_path.is_file() => return Ok(Value::Null),
+        None => fs::read_to_string(&config_path)
+            .with_context(|| format!("Could not read config file {}", config_path.display()))?,
+    };
+
+    serde_yaml::from_str(&contents)
+        .map_err(|e| ObxError::Parse(format!("Invalid config: {e}")).into())
+}
+
+impl Config {
+    /// The config with each vault's settings merged with the global defaults, i.e. the
+    /// settings commands actually use
+    pub fn effective(&self) -> Config {
+        let mut config = self.clone();
+        for vault in &mut config.vaults {
+            vault.settings = vault.settings.merged_with(&self.defaults);
+        }
+        config
+    }
+}
+
 pub fn exists() -> bool {
     let config_path = get_config_path();
     Path::exists(&config_path)
 }
 
 pub fn write(new_config: &Config) -> anyhow::Result<()> {
+    write_value(&serde_yaml::to_value(new_config)?)
+}
+
+/// Check a config changed as YAML, such as one from `read_raw`, is still a valid `Config`
+pub fn validate(config: &Value) -> anyhow::Result<Config> {
+    serde_yaml::from_value(config.clone())
+        .map_err(|e| ObxError::Parse(format!("Invalid config: {e}")).into())
+}
+
+/// Write a config changed as YAML once it's been validated. Its keys are written in the
+/// order they're in, along with any obx doesn't know about
+pub fn write_value(new_config: &Value) -> anyhow::Result<()> {
+    validate(new_config)?;
+    write_raw(&serde_yaml::to_string(new_config)?)
+}
+
+/// Write the contents of the config file exactly as they're given
+pub fn write_raw(contents: &str) -> anyhow::Result<()> {
     let config_path = get_config_path();
-    let serialized = serde_yaml::to_string(new_config)?;
 
-    fs::write(&config_path, serialized)
+    fs::write(&config_path, contents)
         .with_context(|| format!("failed to write to config file {}", config_path.display()))
 }
 
+/// The name a vault gets when none is given, the last segment of its path
+pub fn vault_name_from_path(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "vault".to_string())
+}
+
 impl TryFrom<app_settings::Settings> for Config {
     type Error = anyhow::Error;
 
     fn try_from(settings: app_settings::Settings) -> Result<Self, Self::Error> {
-        let vaults: Vec<Vault> = Vec::from_iter(settings.vaults.values().map(|vault| {
-            Vault {
-                // @TODO: fn to get name from path
-                name: vault
-                    .path
-                    .components()
-                    .last()
-                    .unwrap()
-                    .as_os_str()
-                    .to_str()
-                    .unwrap()
-                    .to_string(),
+        let vaults: Vec<Vault> =
+            Vec::from_iter(settings.recent_vaults().iter().map(|vault| Vault {
+                name: vault_name_from_path(&vault.path),
                 path: PathBuf::from(&vault.path),
-            }
-        }));
+                ..Default::default()
+            }));
+
+        let current_vault = settings
+            .open_vault()
+            .map(|vault| vault_name_from_path(&vault.path));
 
-        match vaults.len() {
-            0 => {
+        match (vaults.len(), current_vault) {
+            (0, _) | (_, None) => {
                 // We can't set a current vault without having at least one
                 // in future if cfg.current_vault is set to optional we
                 // could remove this
                 bail!("Settings must contain at least one vault")
             }
-            _n => {
+            (_n, Some(current_vault)) => {
                 let config = Self {
-                    current_vault: vaults[0].clone().name,
+                    version: CONFIG_VERSION,
+                    current_vault,
                     vaults,
+                    defaults: VaultSettings::default(),
+                    editor_line_pattern: None,
                 };
 
                 Ok(config)
@@ -110,10 +725,11 @@ impl TryFrom<app_settings::Settings> for Config {
 }
 
 pub fn create_from_settings() -> anyhow::Result<Config> {
-    let config_dir = get_config_dir();
     let config_path = get_config_path();
 
-    fs::create_dir_all(config_dir)?;
+    if let Some(config_dir) = config_path.parent() {
+        fs::create_dir_all(config_dir)?;
+    }
 
     fs::OpenOptions::new()
         .create(true)
@@ -132,7 +748,114 @@ pub fn create_from_settings() -> anyhow::Result<Config> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use indoc::indoc;
     use regex::Regex;
+    use test_case::test_case;
+
+    #[test_case("CURRENT_VAULT", Some("current_vault") ; "top level")]
+    #[test_case("VAULTS__0__PATH", Some("vaults[0].path") ; "list item")]
+    #[test_case("TIMESTAMPS__UPDATED_KEY", Some("timestamps.updated_key") ; "nested")]
+    #[test_case("VAULTS____PATH", None ; "empty segment")]
+    #[test_case("0__PATH", None ; "starts with index")]
+    fn env_key_path_maps_to_config_paths(key: &str, expected: Option<&str>) {
+        assert_eq!(env_key_path(key).as_deref(), expected);
+    }
+
+    fn timestamps(enabled: bool) -> Option<Timestamps> {
+        Some(Timestamps::Settings(TimestampSettings {
+            enabled,
+            ..Default::default()
+        }))
+    }
+
+    #[test_case(None, None, true ; "on by default")]
+    #[test_case(None, timestamps(true), true ; "inherited")]
+    #[test_case(None, Some(Timestamps::Enabled(false)), false ; "every vault opts out")]
+    #[test_case(Some(Timestamps::Enabled(false)), timestamps(true), false ; "vault opts out")]
+    #[test_case(Some(Timestamps::Enabled(false)), None, false ; "vault opts out of the defaults")]
+    #[test_case(Some(Timestamps::Enabled(true)), timestamps(false), true ; "vault opts in")]
+    #[test_case(timestamps(true), timestamps(false), true ; "vault's own settings")]
+    fn vaults_inherit_timestamp_settings(
+        vault: Option<Timestamps>,
+        defaults: Option<Timestamps>,
+        enabled: bool,
+    ) {
+        let vault = VaultSettings {
+            timestamps: vault,
+            ..Default::default()
+        };
+        let defaults = VaultSettings {
+            timestamps: defaults,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            vault.merged_with(&defaults).timestamp_settings().is_some(),
+            enabled
+        );
+    }
+
+    #[test]
+    fn migrate_adds_version_first() {
+        let mut config: Mapping =
+            serde_yaml::from_str("current_vault: main\nvaults: []\n").unwrap();
+
+        let applied = migrate(&mut config);
+
+        assert_eq!(applied.len(), 1);
+        assert_eq!(
+            serde_yaml::to_string(&config).unwrap(),
+            "version: 1\ncurrent_vault: main\nvaults: []\n"
+        );
+    }
+
+    #[test]
+    fn migrate_skips_current_configs() {
+        let mut config: Mapping =
+            serde_yaml::from_str("version: 1\ncurrent_vault: main\nvaults: []\n").unwrap();
+
+        assert!(migrate(&mut config).is_empty());
+    }
+
+    #[test_case("# My vaults\ncurrent_vault: main\n", "version: 1\n# My vaults\ncurrent_vault: main\n" ; "adds version first")]
+    #[test_case("---\ncurrent_vault: main\n", "---\nversion: 1\ncurrent_vault: main\n" ; "after document marker")]
+    #[test_case("current_vault: main # mine\nversion: 0\n", "current_vault: main # mine\nversion: 1\n" ; "replaces old version")]
+    fn migrated_text_keeps_comments(contents: &str, expected: &str) {
+        let mut config: Mapping = serde_yaml::from_str(contents).unwrap();
+        migrate(&mut config);
+
+        assert_eq!(migrated_text(contents, &config).unwrap(), expected);
+    }
+
+    #[test]
+    fn warns_about_unknown_keys() {
+        let warnings = config_warnings(indoc! {r#"
+            version: 1
+            current_vault: main
+            vaults:
+            - name: main
+              path: /vault
+              colour: blue
+            notes_folder: ~
+            new_setting: true
+        "#});
+
+        assert_eq!(
+            warnings,
+            vec![
+                "Ignoring unknown config key `vaults.0.colour`",
+                "Ignoring unknown config key `new_setting`",
+            ]
+        );
+    }
+
+    #[test]
+    fn warns_about_newer_versions() {
+        let warnings = config_warnings("version: 99\ncurrent_vault: main\nvaults: []\n");
+
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Config is version 99"));
+    }
 
     #[test]
     #[cfg(target_os = "macos")]
@@ -1,4 +1,6 @@
 pub mod config;
+pub mod graph;
 pub mod init;
 pub mod notes;
+pub mod tasks;
 pub mod vaults;
@@ -1,6 +1,16 @@
-use crate::{cli_config, util::CommandResult};
-use anyhow::Context;
+use crate::{
+    cli_config::{self, Config},
+    editor::open_in_editor,
+    error::ObxError,
+    output::Output,
+    util::{get_current_vault, should_enable_interactivity, CommandResult},
+};
+use anyhow::{anyhow, bail, Context};
 use clap::{Args, Subcommand};
+use dialoguer::Confirm;
+use serde_json::json;
+use serde_yaml::{Mapping, Value};
+use std::{fs, path::Path};
 
 #[derive(Args, Debug, Clone)]
 #[command(args_conflicts_with_subcommands = true)]
@@ -13,41 +23,68 @@ pub struct ConfigCommand {
 #[derive(Debug, Subcommand, Clone)]
 enum Subcommands {
     /// Print the current configuration
-    Print(PrintArgs),
+    Print,
 
     /// Print the absolute path to your config file
     Path,
+
+    /// Print a single config value, e.g. `current_vault` or `vaults.0.path`
+    Get(GetArgs),
+
+    /// Set a config value, the value is parsed as YAML
+    Set(SetArgs),
+
+    /// Remove a config value
+    Unset(GetArgs),
+
+    /// Open your config file in $EDITOR, checking it's valid before saving
+    Edit,
+
+    /// Upgrade your config file to the current version, keeping a backup of the original
+    Migrate(MigrateArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+struct GetArgs {
+    /// A dotted path to the value, e.g. `timestamps.format`
+    key: String,
 }
 
-#[derive(clap::ValueEnum, Clone, Debug)]
-enum PrintFormats {
-    Yaml,
-    Json,
+#[derive(Args, Debug, Clone)]
+struct SetArgs {
+    /// A dotted path to the value, e.g. `timestamps.format`
+    key: String,
+
+    value: String,
 }
 
 #[derive(Args, Debug, Clone)]
-struct PrintArgs {
-    #[arg(long, short = 'f', default_value = "yaml")]
-    format: PrintFormats,
+struct MigrateArgs {
+    /// Print the migrated config without changing the file
+    #[arg(long)]
+    dry_run: bool,
 }
 
-pub fn entry(cmd: &ConfigCommand) -> anyhow::Result<Option<String>> {
+pub fn entry(cmd: &ConfigCommand) -> CommandResult {
     match &cmd.command {
-        Some(Subcommands::Print(PrintArgs { format })) => print(format),
+        Some(Subcommands::Print) => print(),
         Some(Subcommands::Path) => path(),
-        None => todo!(),
+        Some(Subcommands::Get(GetArgs { key })) => get(key),
+        Some(Subcommands::Set(SetArgs { key, value })) => set(key, value),
+        Some(Subcommands::Unset(GetArgs { key })) => unset(key),
+        Some(Subcommands::Edit) => edit(),
+        Some(Subcommands::Migrate(MigrateArgs { dry_run })) => migrate(*dry_run),
+        None => bail!("A config command is required, see `obx config --help`"),
     }
 }
 
-fn print(format: &PrintFormats) -> CommandResult {
-    let config = cli_config::read()?;
+fn print() -> CommandResult {
+    let config = cli_config::read()?.effective();
 
-    let res = match format {
-        PrintFormats::Yaml => serde_yaml::to_string(&config)?,
-        PrintFormats::Json => serde_json::to_string(&config)?,
-    };
+    // The config is YAML, so that's how it's shown unless another format is asked for
+    let yaml = serde_yaml::to_string(&config)?;
 
-    Ok(Some(res))
+    Ok(Some(Output::text(&config, yaml)?))
 }
 
 fn path() -> CommandResult {
@@ -56,5 +93,346 @@ fn path() -> CommandResult {
         .context("failed to stringify config path")?
         .to_string();
 
-    Ok(Some(config_path))
+    Ok(Some(Output::text(
+        json!({ "path": config_path }),
+        config_path,
+    )?))
+}
+
+/// Split a dotted key such as `vaults.0.path` into its segments
+fn key_segments(key: &str) -> anyhow::Result<Vec<&str>> {
+    let segments: Vec<&str> = key.split('.').collect();
+
+    if segments.iter().any(|segment| segment.trim().is_empty()) {
+        bail!("Invalid config key `{key}`");
+    }
+
+    Ok(segments)
+}
+
+fn child<'a>(value: &'a Value, segment: &str) -> Option<&'a Value> {
+    match value {
+        Value::Mapping(mapping) => mapping.get(segment),
+        Value::Sequence(sequence) => sequence.get(segment.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
+fn child_mut<'a>(value: &'a mut Value, segment: &str) -> Option<&'a mut Value> {
+    match value {
+        Value::Mapping(mapping) => mapping.get_mut(segment),
+        Value::Sequence(sequence) => sequence.get_mut(segment.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
+fn get_key<'a>(value: &'a Value, segments: &[&str]) -> Option<&'a Value> {
+    segments
+        .iter()
+        .try_fold(value, |current, segment| child(current, segment))
+}
+
+/// Set the value at a key, creating any missing sections along the way. Existing keys
+/// are replaced in place so the order of the config file is kept
+fn set_key(root: &mut Value, segments: &[&str], new_value: Value) -> anyhow::Result<()> {
+    let Some((last, parents)) = segments.split_last() else {
+        bail!("Invalid config key");
+    };
+
+    let mut current = root;
+    for (idx, segment) in parents.iter().enumerate() {
+        if current.is_null() {
+            *current = Value::Mapping(Mapping::new());
+        }
+
+        if let Value::Mapping(mapping) = current {
+            if !mapping.contains_key(*segment) {
+                mapping.insert(Value::from(*segment), Value::Mapping(Mapping::new()));
+            }
+        }
+
+        current = child_mut(current, segment)
+            .ok_or_else(|| anyhow!("Unknown config key `{}`", segments[..=idx].join(".")))?;
+    }
+
+    if current.is_null() {
+        *current = Value::Mapping(Mapping::new());
+    }
+
+    match current {
+        Value::Mapping(mapping) => {
+            mapping.insert(Value::from(*last), new_value);
+        }
+        Value::Sequence(sequence) => {
+            let slot = last
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| sequence.get_mut(idx))
+                .ok_or_else(|| anyhow!("Unknown config key `{}`", segments.join(".")))?;
+            *slot = new_value;
+        }
+        _ => bail!(
+            "Can't set `{}`, its parent isn't a section",
+            segments.join(".")
+        ),
+    }
+
+    Ok(())
+}
+
+/// Remove the value at a key, returning whether anything was removed
+fn unset_key(root: &mut Value, segments: &[&str]) -> bool {
+    let Some((last, parents)) = segments.split_last() else {
+        return false;
+    };
+
+    let parent = parents
+        .iter()
+        .try_fold(root, |current, segment| child_mut(current, segment));
+
+    match parent {
+        Some(Value::Mapping(mapping)) => mapping.shift_remove(*last).is_some(),
+        Some(Value::Sequence(sequence)) => match last.parse::<usize>() {
+            Ok(idx) if idx < sequence.len() => {
+                sequence.remove(idx);
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// The config as a YAML value, either as commands see it or exactly as it's saved in
+/// the file
+fn config_value(persisted: bool) -> anyhow::Result<Value> {
+    if persisted {
+        return cli_config::read_raw();
+    }
+
+    serde_yaml::to_value(cli_config::read()?).context("failed to serialize config")
+}
+
+fn get(key: &str) -> CommandResult {
+    let segments = key_segments(key)?;
+    let config = config_value(false)?;
+
+    let value = get_key(&config, &segments).ok_or_else(|| anyhow!("Unknown config key `{key}`"))?;
+
+    let formatted = match value {
+        Value::String(s) => s.clone(),
+        other => serde_yaml::to_string(other)?.trim_end().to_string(),
+    };
+
+    Ok(Some(Output::text(value, formatted)?))
+}
+
+fn set(key: &str, value: &str) -> CommandResult {
+    let segments = key_segments(key)?;
+    let mut config = config_value(true)?;
+
+    // Values are YAML so lists and booleans can be set, but anything that doesn't
+    // parse is kept as the string that was typed
+    let new_value = serde_yaml::from_str(value).unwrap_or_else(|_| Value::from(value));
+
+    set_key(&mut config, &segments, new_value)?;
+    let validated = cli_config::validate(&config)?;
+
+    // Keys the config doesn't know about would be ignored, so they're most likely typos
+    if get_key(&serde_yaml::to_value(&validated)?, &segments).is_none() {
+        bail!("Unknown config key `{key}`");
+    }
+
+    cli_config::write_value(&config)?;
+
+    Ok(Some(Output::message(format!("Set {key} to {value}"))))
+}
+
+fn unset(key: &str) -> CommandResult {
+    let segments = key_segments(key)?;
+    let mut config = config_value(true)?;
+
+    if !unset_key(&mut config, &segments) {
+        bail!("Unknown config key `{key}`");
+    }
+
+    cli_config::write_value(&config)?;
+
+    Ok(Some(Output::message(format!("Unset {key}"))))
+}
+
+fn edit() -> CommandResult {
+    let config_path = cli_config::get_config_path();
+    let original = fs::read_to_string(&config_path)
+        .with_context(|| format!("Could not read config file {}", config_path.display()))?;
+
+    // Edit a copy so an invalid config is never left in place
+    let edit_path = config_path.with_extension("edit.yml");
+    fs::write(&edit_path, &original)
+        .with_context(|| format!("Could not write {}", edit_path.display()))?;
+
+    let result = edit_until_valid(&edit_path);
+    let _ = fs::remove_file(&edit_path);
+
+    let edited = result?;
+    if edited == original {
+        return Ok(Some(Output::message("No changes to config")));
+    }
+
+    // It's already been validated, and writing it as it is keeps the user's comments
+    cli_config::write_raw(&edited)?;
+
+    Ok(Some(Output::message(format!(
+        "Saved config {}",
+        config_path.display()
+    ))))
+}
+
+fn migrate(dry_run: bool) -> CommandResult {
+    let plan = if dry_run {
+        cli_config::plan_migration()?
+    } else {
+        cli_config::migrate_file()?
+    };
+
+    let Some(plan) = plan else {
+        return Ok(Some(Output::message(format!(
+            "Config is already at version {}",
+            cli_config::CONFIG_VERSION
+        ))));
+    };
+
+    let steps = plan
+        .migrations
+        .iter()
+        .map(|migration| format!("  {}: {}", migration.version, migration.description))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let backup_path = cli_config::backup_path(&cli_config::get_config_path());
+
+    let text = if dry_run {
+        format!(
+            "Would migrate config from version {} to {}:\n{steps}\n\n{}",
+            plan.from,
+            cli_config::CONFIG_VERSION,
+            plan.migrated.trim_end()
+        )
+    } else {
+        format!(
+            "Migrated config from version {} to {}:\n{steps}\nThe original was saved to {}",
+            plan.from,
+            cli_config::CONFIG_VERSION,
+            backup_path.display()
+        )
+    };
+
+    let migrations: Vec<serde_json::Value> = plan
+        .migrations
+        .iter()
+        .map(|migration| {
+            json!({
+                "version": migration.version,
+                "description": migration.description,
+            })
+        })
+        .collect();
+
+    let data = json!({
+        "from": plan.from,
+        "to": cli_config::CONFIG_VERSION,
+        "migrations": migrations,
+        "backup": (!dry_run).then_some(backup_path),
+        "config": plan.migrated,
+    });
+
+    Ok(Some(Output::text(data, text)?))
+}
+
+/// Open the file in the editor, offering to re-open it for as long as it doesn't parse
+fn edit_until_valid(edit_path: &Path) -> anyhow::Result<String> {
+    // The config may not be valid yet, in which case fall back to $EDITOR
+    let editor = get_current_vault()
+        .ok()
+        .and_then(|vault| vault.settings.editor);
+
+    loop {
+        let status = open_in_editor(edit_path, None, editor.as_deref())?;
+        if !status.success() {
+            bail!(ObxError::EditorFailed(format!(
+                "Editor exited with {status}, config left unchanged"
+            )));
+        }
+
+        let edited = fs::read_to_string(edit_path)
+            .with_context(|| format!("Could not read {}", edit_path.display()))?;
+
+        let error = match serde_yaml::from_str::<Config>(&edited) {
+            Ok(_) => return Ok(edited),
+            Err(e) => anyhow::Error::from(ObxError::Parse(format!("Invalid config: {e}"))),
+        };
+
+        if !should_enable_interactivity() {
+            return Err(error);
+        }
+
+        eprintln!("{error}");
+        let edit_again = Confirm::new()
+            .with_prompt("Edit the config again?")
+            .default(true)
+            .interact()?;
+
+        if !edit_again {
+            return Err(error.context("Config left unchanged"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn example() -> Value {
+        serde_yaml::from_str("current_vault: main\nvaults:\n- name: main\n  path: /vault\n")
+            .unwrap()
+    }
+
+    #[test_case("current_vault", Some("main") ; "top level")]
+    #[test_case("vaults.0.name", Some("main") ; "sequence index")]
+    #[test_case("vaults.1.name", None ; "index out of range")]
+    #[test_case("missing.key", None ; "missing")]
+    fn get_key_follows_dotted_paths(key: &str, expected: Option<&str>) {
+        let config = example();
+        let value = get_key(&config, &key_segments(key).unwrap());
+
+        assert_eq!(value.and_then(Value::as_str), expected);
+    }
+
+    #[test]
+    fn set_key_keeps_order_and_creates_sections() {
+        let mut config = example();
+
+        set_key(&mut config, &["current_vault"], Value::from("other")).unwrap();
+        set_key(&mut config, &["timestamps", "format"], Value::from("%Y")).unwrap();
+
+        assert_eq!(
+            serde_yaml::to_string(&config).unwrap(),
+            "current_vault: other\nvaults:\n- name: main\n  path: /vault\ntimestamps:\n  format: '%Y'\n"
+        );
+    }
+
+    #[test]
+    fn unset_key_removes_values() {
+        let mut config = example();
+
+        assert!(unset_key(&mut config, &["vaults", "0", "path"]));
+        assert!(!unset_key(&mut config, &["vaults", "0", "path"]));
+        assert_eq!(get_key(&config, &["vaults", "0", "path"]), None);
+    }
+
+    #[test]
+    fn key_segments_rejects_empty_segments() {
+        assert!(key_segments("vaults..name").is_err());
+    }
 }
@@ -0,0 +1,547 @@
+use crate::{
+    cli_config::Vault,
+    error::ObxError,
+    formats::{yaml_to_json_value, yaml_to_string_map},
+    graph::{Edge, VaultGraph},
+    output::{self, Format, Output},
+    util::{get_current_vault, resolve_note, CommandResult},
+    vault::{read_notes, tag_matches},
+};
+use anyhow::{bail, Context};
+use clap::{Args, Subcommand};
+use serde_json::json;
+use std::{collections::HashSet, fs, path::PathBuf};
+
+#[derive(Args, Debug, Clone)]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(arg_required_else_help = true)]
+pub struct GraphCommand {
+    #[command(subcommand)]
+    command: Option<Subcommands>,
+}
+
+impl GraphCommand {
+    /// Whether this is `graph export`, the only command that can write the graph formats
+    pub fn is_export(&self) -> bool {
+        matches!(self.command, Some(Subcommands::Export(_)))
+    }
+}
+
+#[derive(Debug, Subcommand, Clone)]
+enum Subcommands {
+    /// Export the graph of notes and the links between them, for use in tools
+    /// such as Graphviz or Gephi
+    Export(ExportArgs),
+    /// Summarise how well connected the vault is
+    Stats,
+    /// List notes with no links in or out
+    Orphans,
+    /// List notes that are linked to, but don't link to anything else
+    DeadEnds,
+    /// List the most linked-to notes, ranked by backlinks and PageRank
+    Hubs(HubsArgs),
+    /// List groups of notes that are connected to each other, largest first
+    Components,
+    /// Find the shortest chain of links from one note to another
+    Path(PathArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+struct HubsArgs {
+    /// How many notes to list
+    #[arg(long, short = 'n', default_value_t = 10)]
+    limit: usize,
+}
+
+#[derive(Args, Debug, Clone)]
+struct PathArgs {
+    /// The note to start from
+    from: String,
+
+    /// The note to finish at
+    to: String,
+
+    /// Follow links backwards as well as forwards
+    #[arg(long)]
+    undirected: bool,
+}
+
+/// Options for limiting the graph to part of the vault
+#[derive(Args, Debug, Clone)]
+struct FilterArgs {
+    /// Only include notes within this folder
+    #[arg(long)]
+    folder: Option<PathBuf>,
+
+    /// Only include notes with this tag
+    #[arg(long, short = 't')]
+    tag: Option<String>,
+
+    /// Only include notes within --hops links of this note
+    #[arg(long)]
+    from: Option<String>,
+
+    /// How many links away from --from to include
+    #[arg(long, default_value_t = 1)]
+    hops: usize,
+}
+
+#[derive(Args, Debug, Clone)]
+struct ExportArgs {
+    /// Write the graph to a file instead of printing it
+    #[arg(long, short = 'o')]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    filters: FilterArgs,
+}
+
+pub fn entry(cmd: &GraphCommand) -> CommandResult {
+    let vault = get_current_vault()?;
+
+    match &cmd.command {
+        Some(Subcommands::Export(args)) => export(args, &vault),
+        Some(Subcommands::Stats) => stats(&vault),
+        Some(Subcommands::Orphans) => orphans(&vault),
+        Some(Subcommands::DeadEnds) => dead_ends(&vault),
+        Some(Subcommands::Hubs(args)) => hubs(args, &vault),
+        Some(Subcommands::Components) => components(&vault),
+        Some(Subcommands::Path(args)) => path(args, &vault),
+        None => anyhow::bail!("A graph command is required, see `obx graph --help`"),
+    }
+}
+
+fn build_graph(vault: &Vault) -> anyhow::Result<VaultGraph> {
+    let notes = read_notes(vault)?;
+
+    Ok(VaultGraph::build(notes))
+}
+
+/// The graph index of the note a user means by `query`, found the same way as in every
+/// other command, so aliases match and ambiguous names are asked about
+fn resolve_in_graph(graph: &VaultGraph, query: &str, vault: &Vault) -> anyhow::Result<usize> {
+    let path = resolve_note(query, vault)?;
+
+    graph
+        .notes
+        .iter()
+        .position(|note| note.path == path)
+        .ok_or_else(|| ObxError::NoteNotFound(query.to_string()).into())
+}
+
+/// The indexes of the notes that pass every filter, in vault order
+fn filter_notes(
+    graph: &VaultGraph,
+    filters: &FilterArgs,
+    vault: &Vault,
+) -> anyhow::Result<Vec<usize>> {
+    let neighbourhood = match &filters.from {
+        Some(seed) => {
+            let seed_idx = resolve_in_graph(graph, seed, vault)?;
+            Some(graph.neighbourhood(seed_idx, filters.hops))
+        }
+        None => None,
+    };
+
+    let included = (0..graph.notes.len())
+        .filter(|idx| {
+            let note = &graph.notes[*idx];
+
+            let in_folder = filters
+                .folder
+                .as_ref()
+                .is_none_or(|folder| note.relative_path.starts_with(folder));
+
+            let has_tag = filters
+                .tag
+                .as_ref()
+                .is_none_or(|filter| note.tags().iter().any(|t| tag_matches(t, filter)));
+
+            let is_nearby = neighbourhood.as_ref().is_none_or(|n| n.contains(idx));
+
+            in_folder && has_tag && is_nearby
+        })
+        .collect();
+
+    Ok(included)
+}
+
+fn export(args: &ExportArgs, vault: &Vault) -> CommandResult {
+    // The file format comes from `--format`. Only an explicit `--format` counts, a vault's
+    // `output_format` shouldn't change exports
+    let graph_format = match output::format_override() {
+        Some(format @ (Format::Dot | Format::Graphml | Format::Json)) => format,
+        _ => bail!("Choose a format to export the graph as with --format dot, graphml or json"),
+    };
+
+    let graph = build_graph(vault)?;
+    let nodes = filter_notes(&graph, &args.filters, vault)?;
+
+    let exported = match graph_format {
+        Format::Dot => export_dot(&graph, &nodes),
+        Format::Graphml => export_graphml(&graph, &nodes),
+        _ => export_json(&graph, &nodes)?,
+    };
+
+    match &args.output {
+        Some(output) => {
+            fs::write(output, format!("{exported}\n"))
+                .with_context(|| format!("Could not write to {}", output.display()))?;
+
+            Ok(Some(Output::message(format!(
+                "Exported {} notes to {}",
+                nodes.len(),
+                output.display()
+            ))))
+        }
+        None => Ok(Some(Output::document(exported))),
+    }
+}
+
+/// Properties flattened to strings, sorted by name, for formats without nested values.
+/// The `tags` property is left out as it's exported separately, along with inline tags
+fn string_properties(graph: &VaultGraph, idx: usize) -> Vec<(String, String)> {
+    let mut properties: Vec<(String, String)> = match graph.notes[idx].properties() {
+        Some(serde_yaml::Value::Mapping(mapping)) => yaml_to_string_map(&mapping)
+            .into_iter()
+            .filter(|(key, _)| key != "tags")
+            .collect(),
+        _ => vec![],
+    };
+
+    properties.sort();
+    properties
+}
+
+/// Edges where both the source and target are included in the export
+fn included_edges<'a>(graph: &'a VaultGraph, nodes: &'a [usize]) -> impl Iterator<Item = &'a Edge> {
+    let included: HashSet<usize> = nodes.iter().copied().collect();
+
+    graph
+        .edges
+        .iter()
+        .filter(move |e| included.contains(&e.source) && included.contains(&e.target))
+}
+
+fn export_json(graph: &VaultGraph, nodes: &[usize]) -> anyhow::Result<String> {
+    let json_nodes: Vec<serde_json::Value> = nodes
+        .iter()
+        .map(|idx| {
+            let note = &graph.notes[*idx];
+            let properties = note
+                .properties()
+                .map(|yaml| yaml_to_json_value(&yaml))
+                .unwrap_or_else(|| serde_json::Value::Object(serde_json::Map::new()));
+
+            json!({
+                "id": graph.id(*idx),
+                "title": note.title(),
+                "path": note.relative_path,
+                "tags": note.tags(),
+                "properties": properties,
+            })
+        })
+        .collect();
+
+    let json_edges: Vec<serde_json::Value> = included_edges(graph, nodes)
+        .map(|edge| {
+            json!({
+                "source": graph.id(edge.source),
+                "target": graph.id(edge.target),
+                "kind": edge.kind,
+            })
+        })
+        .collect();
+
+    let exported = serde_json::to_string(&json!({
+        "nodes": json_nodes,
+        "edges": json_edges,
+    }))?;
+
+    Ok(exported)
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn export_dot(graph: &VaultGraph, nodes: &[usize]) -> String {
+    let mut lines = vec!["digraph vault {".to_string()];
+
+    for idx in nodes {
+        let note = &graph.notes[*idx];
+        let mut attributes = vec![format!("label=\"{}\"", dot_escape(&note.title()))];
+
+        let tags = note.tags();
+        if !tags.is_empty() {
+            attributes.push(format!("tags=\"{}\"", dot_escape(&tags.join(", "))));
+        }
+
+        for (key, value) in string_properties(graph, *idx) {
+            attributes.push(format!(
+                "\"{}\"=\"{}\"",
+                dot_escape(&key),
+                dot_escape(&value)
+            ));
+        }
+
+        lines.push(format!(
+            "  \"{}\" [{}];",
+            dot_escape(&graph.id(*idx)),
+            attributes.join(", ")
+        ));
+    }
+
+    for edge in included_edges(graph, nodes) {
+        lines.push(format!(
+            "  \"{}\" -> \"{}\" [kind=\"{}\"];",
+            dot_escape(&graph.id(edge.source)),
+            dot_escape(&graph.id(edge.target)),
+            edge.kind.as_str()
+        ));
+    }
+
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn export_graphml(graph: &VaultGraph, nodes: &[usize]) -> String {
+    let node_properties: Vec<(usize, Vec<(String, String)>)> = nodes
+        .iter()
+        .map(|idx| (*idx, string_properties(graph, *idx)))
+        .collect();
+
+    // GraphML needs every attribute declared up-front as a <key>
+    let mut property_names: Vec<String> = node_properties
+        .iter()
+        .flat_map(|(_, properties)| properties.iter().map(|(k, _)| k.clone()))
+        .collect::<HashSet<String>>()
+        .into_iter()
+        .collect();
+    property_names.sort();
+
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#.to_string(),
+        r#"  <key id="label" for="node" attr.name="label" attr.type="string"/>"#.to_string(),
+        r#"  <key id="path" for="node" attr.name="path" attr.type="string"/>"#.to_string(),
+        r#"  <key id="tags" for="node" attr.name="tags" attr.type="string"/>"#.to_string(),
+        r#"  <key id="kind" for="edge" attr.name="kind" attr.type="string"/>"#.to_string(),
+    ];
+
+    for (i, name) in property_names.iter().enumerate() {
+        lines.push(format!(
+            r#"  <key id="p{i}" for="node" attr.name="{}" attr.type="string"/>"#,
+            xml_escape(name)
+        ));
+    }
+
+    lines.push(r#"  <graph id="vault" edgedefault="directed">"#.to_string());
+
+    for (idx, properties) in &node_properties {
+        let note = &graph.notes[*idx];
+
+        lines.push(format!(
+            r#"    <node id="{}">"#,
+            xml_escape(&graph.id(*idx))
+        ));
+        lines.push(format!(
+            r#"      <data key="label">{}</data>"#,
+            xml_escape(&note.title())
+        ));
+        lines.push(format!(
+            r#"      <data key="path">{}</data>"#,
+            xml_escape(&note.relative_path.display().to_string())
+        ));
+
+        let tags = note.tags();
+        if !tags.is_empty() {
+            lines.push(format!(
+                r#"      <data key="tags">{}</data>"#,
+                xml_escape(&tags.join(", "))
+            ));
+        }
+
+        for (key, value) in properties {
+            let key_idx = property_names
+                .iter()
+                .position(|name| name == key)
+                .expect("property names should include every property");
+
+            lines.push(format!(
+                r#"      <data key="p{key_idx}">{}</data>"#,
+                xml_escape(value)
+            ));
+        }
+
+        lines.push("    </node>".to_string());
+    }
+
+    for edge in included_edges(graph, nodes) {
+        lines.push(format!(
+            r#"    <edge source="{}" target="{}">"#,
+            xml_escape(&graph.id(edge.source)),
+            xml_escape(&graph.id(edge.target))
+        ));
+        lines.push(format!(
+            r#"      <data key="kind">{}</data>"#,
+            edge.kind.as_str()
+        ));
+        lines.push("    </edge>".to_string());
+    }
+
+    lines.push("  </graph>".to_string());
+    lines.push("</graphml>".to_string());
+    lines.join("\n")
+}
+
+fn stats(vault: &Vault) -> CommandResult {
+    let graph = build_graph(vault)?;
+    let components = graph.components();
+
+    let summary = [
+        ("notes", graph.notes.len()),
+        ("links", graph.edges.len()),
+        ("orphans", graph.orphans().len()),
+        ("dead_ends", graph.dead_ends().len()),
+        ("components", components.len()),
+        ("largest_component", components.first().map_or(0, Vec::len)),
+    ];
+
+    let data: serde_json::Map<String, serde_json::Value> = summary
+        .iter()
+        .map(|(key, value)| (key.to_string(), json!(value)))
+        .collect();
+
+    let rows = summary
+        .iter()
+        .map(|(key, value)| vec![key.replace('_', " "), value.to_string()])
+        .collect();
+
+    Ok(Some(Output::table(data, &["Metric", "Value"], rows)?))
+}
+
+/// A list of notes as a single column table, or a list of ids
+fn note_list(graph: &VaultGraph, notes: &[usize], empty_message: &str) -> CommandResult {
+    let ids: Vec<String> = notes.iter().map(|idx| graph.id(*idx)).collect();
+
+    if ids.is_empty() {
+        return Ok(Some(Output::text(ids, empty_message)?));
+    }
+
+    let rows = ids.iter().map(|id| vec![id.clone()]).collect();
+
+    Ok(Some(Output::table(ids, &["Note"], rows)?))
+}
+
+fn orphans(vault: &Vault) -> CommandResult {
+    let graph = build_graph(vault)?;
+    note_list(&graph, &graph.orphans(), "No orphaned notes found")
+}
+
+fn dead_ends(vault: &Vault) -> CommandResult {
+    let graph = build_graph(vault)?;
+    note_list(&graph, &graph.dead_ends(), "No dead ends found")
+}
+
+fn hubs(args: &HubsArgs, vault: &Vault) -> CommandResult {
+    let graph = build_graph(vault)?;
+    let ranks = graph.pagerank(0.85, 50);
+
+    let mut hubs: Vec<(usize, usize)> = (0..graph.notes.len())
+        .map(|idx| (idx, graph.backlinked_notes(idx).len()))
+        .filter(|(_, backlinks)| *backlinks > 0)
+        .collect();
+
+    hubs.sort_by(|(a, a_backlinks), (b, b_backlinks)| {
+        b_backlinks
+            .cmp(a_backlinks)
+            .then(ranks[*b].total_cmp(&ranks[*a]))
+            .then(a.cmp(b))
+    });
+    hubs.truncate(args.limit);
+
+    let data: Vec<serde_json::Value> = hubs
+        .iter()
+        .map(|(idx, backlinks)| {
+            json!({
+                "note": graph.id(*idx),
+                "backlinks": backlinks,
+                "pagerank": ranks[*idx],
+            })
+        })
+        .collect();
+
+    if hubs.is_empty() {
+        return Ok(Some(Output::text(data, "No linked notes found")?));
+    }
+
+    let rows = hubs
+        .iter()
+        .map(|(idx, backlinks)| {
+            vec![
+                graph.id(*idx),
+                backlinks.to_string(),
+                format!("{:.4}", ranks[*idx]),
+            ]
+        })
+        .collect();
+
+    Ok(Some(Output::table(
+        data,
+        &["Note", "Backlinks", "PageRank"],
+        rows,
+    )?))
+}
+
+fn components(vault: &Vault) -> CommandResult {
+    let graph = build_graph(vault)?;
+
+    let components: Vec<Vec<String>> = graph
+        .components()
+        .iter()
+        .map(|c| c.iter().map(|idx| graph.id(*idx)).collect())
+        .collect();
+
+    let rows = components
+        .iter()
+        .map(|ids| vec![ids.len().to_string(), ids.join("\n")])
+        .collect();
+
+    Ok(Some(Output::table(components, &["Size", "Notes"], rows)?))
+}
+
+fn path(args: &PathArgs, vault: &Vault) -> CommandResult {
+    let graph = build_graph(vault)?;
+
+    let from = resolve_in_graph(&graph, &args.from, vault)?;
+    let to = resolve_in_graph(&graph, &args.to, vault)?;
+
+    let Some(path) = graph.shortest_path(from, to, args.undirected) else {
+        anyhow::bail!(
+            "No path found from `{}` to `{}`",
+            graph.id(from),
+            graph.id(to)
+        );
+    };
+
+    let ids: Vec<String> = path.iter().map(|idx| graph.id(*idx)).collect();
+
+    let data = json!({
+        "from": graph.id(from),
+        "to": graph.id(to),
+        "length": ids.len() - 1,
+        "path": ids,
+    });
+
+    Ok(Some(Output::text(data, ids.join(" -> "))?))
+}
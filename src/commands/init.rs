@@ -1,6 +1,7 @@
 use crate::{
     cli_config,
     commands::vaults::interactive_switch,
+    output::Output,
     util::{should_enable_interactivity, CommandResult},
 };
 use clap::{ArgAction, Args};
@@ -18,21 +19,24 @@ pub struct InitCommand {
 }
 
 pub fn entry(cmd: &InitCommand) -> CommandResult {
-    let updated_config = create_or_overwrite_config(cmd)?;
+    let (updated_config, message) = create_or_overwrite_config(cmd)?;
 
     if let Some(mut config) = updated_config {
         if !cmd.auto_vault {
             let next_vault =
-                interactive_switch(&config, "Which vault would you like to set as the current");
+                interactive_switch(&config, "Which vault would you like to set as the current")?;
             config.current_vault = next_vault;
         }
         cli_config::write(&config)?;
     }
 
-    Ok(None)
+    Ok(Some(Output::message(message)))
 }
 
-fn create_or_overwrite_config(cmd: &InitCommand) -> anyhow::Result<Option<cli_config::Config>> {
+/// The config to write, if any, and a message saying what happened
+fn create_or_overwrite_config(
+    cmd: &InitCommand,
+) -> anyhow::Result<(Option<cli_config::Config>, String)> {
     let config_file_exists = cli_config::exists();
     let config_path = cli_config::get_config_path();
 
@@ -53,15 +57,13 @@ fn create_or_overwrite_config(cmd: &InitCommand) -> anyhow::Result<Option<cli_co
 
         if confirmation {
             let config = cli_config::create_from_settings()?;
-            println!("Config file overwritten");
-            Ok(Some(config))
+            Ok((Some(config), "Config file overwritten".to_string()))
         } else {
-            println!("Config file left as-is");
-            Ok(None)
+            Ok((None, "Config file left as-is".to_string()))
         }
     } else {
         let config = cli_config::create_from_settings()?;
-        println!("Config file created at {}", config_path.display());
-        Ok(Some(config))
+        let message = format!("Config file created at {}", config_path.display());
+        Ok((Some(config), message))
     }
 }
@@ -1,15 +1,31 @@
 use crate::{
     cli_config,
-    formats::{yaml_to_json_value, yaml_to_string_map},
-    util::{get_current_vault, resolve_note_path, should_enable_interactivity, CommandResult},
+    editor::{anchor_line, line_changes, open_in_editor, split_anchor, Anchor},
+    error::ObxError,
+    formats::{split_frontmatter, yaml_to_json_value, yaml_to_string_map},
+    graph::{note_links, VaultGraph},
+    mentions::{link_mentions, note_mentions, Mention},
+    output::Output,
+    timestamps,
+    util::{
+        get_current_vault, interactive_note_picker, new_note_path, obsidian_note_uri, resolve_note,
+        should_enable_interactivity, CommandResult,
+    },
+    vault::{read_notes, VaultNote},
 };
-use anyhow::{anyhow, Context};
+use anyhow::Context;
 use atty::{isnt, Stream};
+use chrono::{DateTime, Local, SecondsFormat};
 use clap::{Args, Subcommand};
-use dialoguer::Confirm;
+use dialoguer::{Confirm, MultiSelect};
 use libobsidian::{ObsidianNote, Properties};
-use std::{env, fs, io, path::PathBuf, process};
-use tabled::{builder::Builder, settings::Style};
+use serde_json::json;
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+    time::SystemTime,
+};
 
 #[derive(Args, Debug, Clone)]
 #[command(args_conflicts_with_subcommands = true)]
@@ -17,9 +33,6 @@ use tabled::{builder::Builder, settings::Style};
 pub struct NotesCommand {
     #[command(subcommand)]
     command: Option<Subcommands>,
-
-    #[arg(long, short = 'v', global = true)]
-    vault: Option<String>,
 }
 
 #[derive(Debug, Subcommand, Clone)]
@@ -34,9 +47,16 @@ enum Subcommands {
     Uri(UriArgs),
 
     /// Create a new note
+    ///
+    /// Content can be piped in via stdin, either as markdown (optionally with a `---`
+    /// frontmatter block), as a JSON object of properties, or as JSON of the form
+    /// `{"properties": {...}, "body": "..."}`
     Create(CreateArgs),
 
     /// Open a note in your default editor ($EDITOR)
+    ///
+    /// Add `#Heading`, `#^block-id` or `:line` to the note to open the editor at that
+    /// position, e.g. `obx notes edit my-note#Ideas`
     Edit(EditArgs),
 
     /// Print the full file-path of the note
@@ -46,6 +66,12 @@ enum Subcommands {
     // Render(RenderArgs),
     /// View the properties of a note
     Properties(PropertiesArgs),
+
+    /// Find plain-text mentions of a note's title or aliases in other notes
+    ///
+    /// Mentions inside frontmatter, code, existing links and URLs are skipped. With
+    /// `--link` the mentions are replaced with `[[Note|original text]]`
+    Mentions(MentionsArgs),
     // Convert the note to a range of formats
     // Export(ExportArgs),
 
@@ -55,11 +81,9 @@ enum Subcommands {
 
 #[derive(Args, Debug, Clone)]
 struct NoteArgs {
-    #[arg(help = "The path to the note, if the extension is omitted .md will be assumed")]
-    note: String,
-
-    #[arg(long, short = 'v', global = true)]
-    vault: Option<String>,
+    /// The path, name or alias of the note, if the extension is omitted .md will be assumed.
+    /// Leave this out to search for a note interactively
+    note: Option<String>,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -108,18 +132,9 @@ struct RenderArgs {
     common: NoteArgs,
 }
 
-#[derive(clap::ValueEnum, Clone, Debug)]
-enum ExportFormatOption {
-    Pretty,
-    Html,
-    Json,
-}
-
 #[derive(Args, Debug, Clone)]
 struct PropertiesArgs {
-    #[arg(long, short = 'f', default_value = "pretty")]
-    format: ExportFormatOption,
-
+    /// Also show file metadata such as size, word count and backlinks
     #[arg(long)]
     include_meta: bool,
 
@@ -128,25 +143,36 @@ struct PropertiesArgs {
 }
 
 #[derive(Args, Debug, Clone)]
-struct ExportArgs {
+struct MentionsArgs {
+    /// Convert the mentions into links to the note
+    #[arg(long)]
+    link: bool,
+
+    /// Show the changes --link would make without writing them
+    #[arg(long, requires = "link")]
+    dry_run: bool,
+
+    /// Link every mention without asking for confirmation
+    #[arg(long, short = 'y', requires = "link")]
+    yes: bool,
+
     #[command(flatten)]
     common: NoteArgs,
+}
 
-    // No default, must be explicitly selected
-    #[arg(long, short = 'f')]
-    format: ExportFormatOption,
+#[derive(Args, Debug, Clone)]
+struct ExportArgs {
+    #[command(flatten)]
+    common: NoteArgs,
 }
 
 #[derive(Args, Debug, Clone)]
 struct BacklinksArgs {
     #[command(flatten)]
     common: NoteArgs,
-
-    #[arg(long, short = 'f', default_value = "pretty")]
-    format: ExportFormatOption,
 }
 
-pub fn entry(cmd: &NotesCommand) -> anyhow::Result<Option<String>> {
+pub fn entry(cmd: &NotesCommand) -> CommandResult {
     match &cmd.command {
         Some(Subcommands::View(ViewArgs { common })) => {
             let args = EnrichedNoteArgs::from_args(common)?;
@@ -162,15 +188,23 @@ pub fn entry(cmd: &NotesCommand) -> anyhow::Result<Option<String>> {
         }
         Some(Subcommands::Create(CreateArgs { common })) => {
             let stdin = maybe_stdin()?;
-            let args = EnrichedNoteArgs::from_args(common)?;
+            let args = EnrichedNoteArgs::from_exact_path(common)?;
             create(args, stdin)
         }
         Some(Subcommands::Edit(EditArgs {
             common,
             create: should_create,
         })) => {
-            let args = EnrichedNoteArgs::from_args(common)?;
-            edit(args, should_create)
+            let (note, anchor) = match &common.note {
+                Some(note) => {
+                    let (note, anchor) = split_anchor(note);
+                    (Some(note.to_string()), anchor)
+                }
+                None => (None, None),
+            };
+
+            let args = EnrichedNoteArgs::from_args(&NoteArgs { note })?;
+            edit(args, should_create, anchor)
         }
         Some(Subcommands::Path(PathArgs { common })) => {
             let args = EnrichedNoteArgs::from_args(common)?;
@@ -180,9 +214,16 @@ pub fn entry(cmd: &NotesCommand) -> anyhow::Result<Option<String>> {
         //     let args = EnrichedNoteArgs::from_args(common)?;
         //     render(args)
         // }
-        Some(Subcommands::Properties(PropertiesArgs { common, format, .. })) => {
+        Some(Subcommands::Properties(PropertiesArgs {
+            common,
+            include_meta,
+        })) => {
             let args = EnrichedNoteArgs::from_args(common)?;
-            properties(args, format)
+            properties(args, *include_meta)
+        }
+        Some(Subcommands::Mentions(args)) => {
+            let note = EnrichedNoteArgs::from_args(&args.common)?;
+            mentions(note, args)
         }
         // Some(Subcommands::Export(ExportArgs { common, .. })) => {
         //     let args = EnrichedNoteArgs::from_args(common)?;
@@ -192,7 +233,7 @@ pub fn entry(cmd: &NotesCommand) -> anyhow::Result<Option<String>> {
         //     let args = EnrichedNoteArgs::from_args(common)?;
         //     backlinks(args)
         // }
-        None => todo!(),
+        None => anyhow::bail!("A notes command is required, see `obx notes --help`"),
     }
 }
 
@@ -202,18 +243,35 @@ struct EnrichedNoteArgs {
     note_file: String,
 }
 
+type NoteResolver = fn(&str, &cli_config::Vault) -> anyhow::Result<PathBuf>;
+
 impl EnrichedNoteArgs {
+    /// Find the note by path, name or alias
     fn from_args(args: &NoteArgs) -> anyhow::Result<Self> {
-        let vault_name = &args.vault;
-        let vault = get_current_vault(vault_name.clone())?;
+        Self::resolve_with(args, resolve_note)
+    }
+
+    /// Use the path exactly as given, for commands that create new notes
+    fn from_exact_path(args: &NoteArgs) -> anyhow::Result<Self> {
+        if args.note.is_none() {
+            anyhow::bail!("A path is required to create a note");
+        }
+
+        Self::resolve_with(args, new_note_path)
+    }
+
+    fn resolve_with(args: &NoteArgs, resolver: NoteResolver) -> anyhow::Result<Self> {
+        let vault = get_current_vault()?;
 
-        let note_path = resolve_note_path(&args.note, &vault.path)?;
+        let note_path = match &args.note {
+            Some(note) => resolver(note, &vault)?,
+            None => interactive_note_picker(&vault)?,
+        };
         let note_file = note_path
             .file_name()
-            .expect("note_path should be a file")
-            .to_str()
-            .unwrap()
-            .to_owned();
+            .with_context(|| format!("`{}` is not a note", note_path.display()))?
+            .to_string_lossy()
+            .to_string();
 
         Ok(Self {
             vault,
@@ -221,20 +279,30 @@ impl EnrichedNoteArgs {
             note_file,
         })
     }
+
+    /// For commands that read the note, which fail when it doesn't exist rather than
+    /// working with the path a new note would be created at
+    fn ensure_exists(&self) -> anyhow::Result<()> {
+        if !self.note_path.is_file() {
+            anyhow::bail!(ObxError::NoteNotFound(self.note_file.clone()));
+        }
+
+        Ok(())
+    }
 }
 
 fn view(note: EnrichedNoteArgs) -> CommandResult {
+    note.ensure_exists()?;
+
     let note_content = fs::read_to_string(note.note_path.clone())
         .with_context(|| format!("Could not read note `{}`", note.note_file))?;
 
-    Ok(Some(note_content))
-}
+    let data = json!({
+        "path": note.note_path,
+        "content": note_content,
+    });
 
-fn obsidian_note_uri(note_path: &PathBuf, vault: String) -> String {
-    format!(
-        "obsidian://open?vault={vault}&file={file}",
-        file = note_path.display()
-    )
+    Ok(Some(Output::text(data, note_content)?))
 }
 
 fn open(note: EnrichedNoteArgs) -> CommandResult {
@@ -248,7 +316,7 @@ fn open(note: EnrichedNoteArgs) -> CommandResult {
 fn uri(note: EnrichedNoteArgs) -> CommandResult {
     let uri = obsidian_note_uri(&note.note_path, note.vault.name);
 
-    Ok(Some(uri))
+    Ok(Some(Output::text(json!({ "uri": uri }), uri)?))
 }
 
 fn write_note(obsidian_note: &ObsidianNote) -> anyhow::Result<()> {
@@ -292,6 +360,10 @@ fn create_note(note: &EnrichedNoteArgs, note_contents: &str) -> anyhow::Result<(
 }
 
 fn create(note: EnrichedNoteArgs, stdin: Option<String>) -> CommandResult {
+    if note.note_path.exists() {
+        anyhow::bail!("The note {} already exists", note.note_file);
+    }
+
     let (content, properties) = parse_create_stdin(stdin)?;
 
     let obsidian_note = ObsidianNote {
@@ -303,20 +375,74 @@ fn create(note: EnrichedNoteArgs, stdin: Option<String>) -> CommandResult {
 
     write_note(&obsidian_note)?;
 
-    let editor = env::var("EDITOR").context("$EDITOR not found")?;
+    let message = match edit_and_compare(&note, None, true)? {
+        EditOutcome::Discarded => format!("Discarded empty note {}", note.note_path.display()),
+        _ => {
+            timestamps::stamp_file(&note.vault, &note.note_path, true)?;
+            format!("Created note {}", note.note_path.display())
+        }
+    };
 
-    let editor_status = process::Command::new(&editor)
-        .arg(&note.note_path)
-        .status()
-        .with_context(|| format!("failed to execute $EDITOR={editor}"))?;
+    Ok(Some(Output::message(message)))
+}
 
-    if editor_status.success() {
-        // @TODO: this isn't strictly true, discarding changes with :q!
-        // in vim will still show this message
-        Ok(Some(format!("Created note {}", &note.note_path.display())))
+/// What happened to a note while it was open in the editor
+enum EditOutcome {
+    Unchanged,
+    /// The note was created for this edit, and removed again as it was left empty
+    Discarded,
+    Changed {
+        added: usize,
+        removed: usize,
+    },
+}
+
+/// Open the note in `$EDITOR`, comparing it before and after so only changes that were
+/// actually saved are reported. A new note that's still empty afterwards is removed
+fn edit_and_compare(
+    note: &EnrichedNoteArgs,
+    line: Option<usize>,
+    is_new: bool,
+) -> anyhow::Result<EditOutcome> {
+    let read_note = || {
+        fs::read_to_string(&note.note_path)
+            .with_context(|| format!("Could not read note `{}`", note.note_file))
+    };
+
+    let before = read_note()?;
+    let editor_status =
+        open_in_editor(&note.note_path, line, note.vault.settings.editor.as_deref())?;
+    let after = if note.note_path.exists() {
+        read_note()?
     } else {
-        Err(anyhow::Error::msg("Editor exited with non-0 exit code"))
+        String::new()
+    };
+
+    let is_empty_new_note = is_new && after.trim().is_empty();
+    if is_empty_new_note && (after == before || !editor_status.success()) {
+        fs::remove_file(&note.note_path)
+            .with_context(|| format!("Could not remove empty note `{}`", note.note_file))?;
+    }
+
+    if !editor_status.success() {
+        anyhow::bail!(ObxError::EditorFailed(
+            "Editor exited with non-0 exit code".to_string()
+        ));
     }
+
+    if after == before {
+        let outcome = if is_empty_new_note {
+            EditOutcome::Discarded
+        } else {
+            EditOutcome::Unchanged
+        };
+
+        return Ok(outcome);
+    }
+
+    let (added, removed) = line_changes(&before, &after);
+
+    Ok(EditOutcome::Changed { added, removed })
 }
 
 fn parse_create_stdin(
@@ -328,22 +454,67 @@ fn parse_create_stdin(
     // Check if the trimmed input looks like JSON
     if trimmed.starts_with('{') && trimmed.ends_with('}') {
         match serde_json::from_str::<serde_json::Value>(trimmed) {
-            Ok(json) => {
-                let yaml_str = serde_yaml::to_string(&json)?;
-                let yaml_values: serde_yaml::Value = serde_yaml::from_str(&yaml_str)?;
-
-                Ok((Some("".to_string()), Some(yaml_values)))
-            }
-            Err(e) => Err(anyhow!("Input looks like JSON but failed to parse: {}", e)),
+            Ok(json) => parse_json_note(json),
+            Err(e) => Err(ObxError::Parse(format!(
+                "Input looks like JSON but failed to parse: {e}"
+            ))
+            .into()),
         }
+    } else if let Some((frontmatter, body)) = split_frontmatter(&note_input) {
+        let properties: Properties = serde_yaml::from_str(frontmatter).context(ObxError::Parse(
+            "Input has a frontmatter block but it failed to parse as YAML".to_string(),
+        ))?;
+
+        // An empty `---\n---` block parses as null, which we don't want to write back out
+        let properties = match properties {
+            serde_yaml::Value::Null => None,
+            properties => Some(properties),
+        };
+
+        Ok((Some(body.to_string()), properties))
     } else {
         Ok((Some(note_input), None))
     }
 }
 
-fn edit(note: EnrichedNoteArgs, create_flag: &bool) -> CommandResult {
+/// JSON input is either a bare object of properties, or an object of the form
+/// `{"properties": {...}, "body": "..."}` when the note needs content too
+fn parse_json_note(
+    json: serde_json::Value,
+) -> anyhow::Result<(Option<String>, Option<Properties>)> {
+    let is_structured = match &json {
+        serde_json::Value::Object(map) => {
+            !map.is_empty()
+                && map.keys().all(|k| k == "properties" || k == "body")
+                && map.get("properties").is_none_or(|p| p.is_object())
+                && map.get("body").is_none_or(|b| b.is_string())
+        }
+        _ => false,
+    };
+
+    if !is_structured {
+        let yaml_values: Properties = serde_yaml::to_value(&json)?;
+        return Ok((Some("".to_string()), Some(yaml_values)));
+    }
+
+    let body = json
+        .get("body")
+        .and_then(|b| b.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let properties = match json.get("properties") {
+        Some(props) => Some(serde_yaml::to_value(props)?),
+        None => None,
+    };
+
+    Ok((Some(body), properties))
+}
+
+fn edit(note: EnrichedNoteArgs, create_flag: &bool, anchor: Option<Anchor>) -> CommandResult {
     let note_exists = note.note_path.exists();
     let term_is_attended = should_enable_interactivity();
+    let mut is_new = false;
 
     if !note_exists {
         let mut confirmation = false;
@@ -363,76 +534,334 @@ fn edit(note: EnrichedNoteArgs, create_flag: &bool) -> CommandResult {
         if confirmation || *create_flag {
             let note_contents = "";
             create_note(&note, note_contents)?;
+            is_new = true;
         } else {
-            return Ok(Some("Aborted".to_string()));
+            anyhow::bail!(ObxError::Aborted);
         }
     }
 
-    let editor = env::var("EDITOR").context("$EDITOR not found")?;
+    let line = match &anchor {
+        Some(anchor) => {
+            let contents = fs::read_to_string(&note.note_path)
+                .with_context(|| format!("Could not read note `{}`", note.note_file))?;
+
+            let line = anchor_line(&contents, anchor).with_context(|| match anchor {
+                Anchor::Heading(heading) => {
+                    format!("Could not find heading `{heading}` in {}", note.note_file)
+                }
+                Anchor::Block(block) => {
+                    format!("Could not find block `^{block}` in {}", note.note_file)
+                }
+                Anchor::Line(line) => format!("Invalid line {line} in {}", note.note_file),
+            })?;
+
+            Some(line)
+        }
+        None => None,
+    };
 
-    let editor_status = process::Command::new(&editor)
-        .arg(&note.note_path)
-        .status()
-        .with_context(|| format!("failed to execute $EDITOR={editor}"))?;
+    let message = match edit_and_compare(&note, line, is_new)? {
+        EditOutcome::Unchanged => format!("No changes to {}", note.note_file),
+        EditOutcome::Discarded => format!("No changes, discarded empty note {}", note.note_file),
+        EditOutcome::Changed { added, removed } => {
+            timestamps::stamp_file(&note.vault, &note.note_path, is_new)?;
+            format!("Saved changes to {} (+{added} -{removed})", note.note_file)
+        }
+    };
 
-    if editor_status.success() {
-        // @TODO: this isn't strictly true, discarding changes with :q!
-        // in vim will still show this message
-        Ok(Some(format!("Saved changes to {}", &note.note_file)))
-    } else {
-        Err(anyhow::Error::msg("Editor exited with non-0 exit code"))
-    }
+    Ok(Some(Output::message(message)))
 }
 
 fn path(note: EnrichedNoteArgs) -> CommandResult {
-    let note_path = note.note_path.to_str().unwrap().to_string();
-    Ok(Some(note_path))
+    let note_path = note.note_path.display().to_string();
+    Ok(Some(Output::text(json!({ "path": note_path }), note_path)?))
 }
 
-fn properties(note: EnrichedNoteArgs, format: &ExportFormatOption) -> CommandResult {
-    let note =
-        ObsidianNote::read_from_path(&note.note_path).with_context(|| "could not parse note")?;
+/// File metadata for a note, shown alongside its properties with `--include-meta`
+fn note_meta(note: &EnrichedNoteArgs) -> anyhow::Result<serde_json::Value> {
+    let vault_note = VaultNote::read(&note.vault.path, &note.note_path)?;
+    let metadata = fs::metadata(&note.note_path)
+        .with_context(|| format!("Could not read `{}`", note.note_path.display()))?;
 
-    let formatted = match format {
-        ExportFormatOption::Json => {
-            let json_value = note
-                .properties
-                .map(|yaml| yaml_to_json_value(&yaml))
-                .unwrap_or_else(|| serde_json::Value::Object(serde_json::Map::new()));
-            serde_json::to_string(&json_value)?
-        }
-        ExportFormatOption::Pretty => {
-            let Some(serde_yaml::Value::Mapping(properties)) = note.properties else {
-                panic!("Expected note.properties to be yaml::Value::mapping")
-            };
+    let timestamp = |time: io::Result<SystemTime>| {
+        time.ok()
+            .map(|t| DateTime::<Local>::from(t).to_rfc3339_opts(SecondsFormat::Secs, true))
+    };
+
+    let body = vault_note.body();
+
+    let graph = VaultGraph::build(read_notes(&note.vault)?);
+    let backlinks = graph
+        .notes
+        .iter()
+        .position(|n| n.relative_path == vault_note.relative_path)
+        .map_or(0, |idx| graph.backlinked_notes(idx).len());
+
+    Ok(json!({
+        "path": vault_note.path.display().to_string(),
+        "relative_path": vault_note.relative_path.display().to_string(),
+        "size": metadata.len(),
+        "created": timestamp(metadata.created()),
+        "modified": timestamp(metadata.modified()),
+        "words": vault_note.word_count(),
+        "characters": body.chars().count(),
+        "headings": vault_note.heading_count(),
+        "links": note_links(&vault_note).len(),
+        "backlinks": backlinks,
+        "tags": vault_note.tags(),
+    }))
+}
 
-            let mut property_strings = yaml_to_string_map(&properties)
-                .into_iter()
-                .map(|(k, v)| vec![k, v])
-                .collect::<Vec<Vec<String>>>();
+fn properties(note: EnrichedNoteArgs, include_meta: bool) -> CommandResult {
+    note.ensure_exists()?;
 
-            property_strings.sort();
-            let sorted_properties = property_strings.iter();
+    let meta = include_meta.then(|| note_meta(&note)).transpose()?;
 
-            let mut builder = Builder::from_iter(sorted_properties);
-            builder.insert_record(0, vec!["Property", "Value"]);
+    let obsidian_note = ObsidianNote::read_from_path(&note.note_path)
+        .with_context(|| ObxError::Parse(format!("Could not parse note `{}`", note.note_file)))?;
 
-            let mut table = builder.build();
-            table.with(Style::sharp());
+    let mut json_value = obsidian_note
+        .properties
+        .as_ref()
+        .map(yaml_to_json_value)
+        .unwrap_or_else(|| serde_json::Value::Object(serde_json::Map::new()));
 
-            format!("{table}")
+    let properties = match obsidian_note.properties {
+        Some(serde_yaml::Value::Mapping(properties)) => properties,
+        _ => serde_yaml::Mapping::new(),
+    };
+
+    let mut property_strings = yaml_to_string_map(&properties)
+        .into_iter()
+        .map(|(k, v)| vec![k, v])
+        .collect::<Vec<Vec<String>>>();
+
+    property_strings.sort();
+
+    // Meta rows come after the note's own properties, prefixed with `$` so
+    // they can't be mistaken for them
+    if let Some(serde_json::Value::Object(meta)) = &meta {
+        for (key, value) in meta {
+            let value = match value {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => "-".to_string(),
+                serde_json::Value::Array(items) => items
+                    .iter()
+                    .filter_map(|item| item.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", "),
+                other => other.to_string(),
+            };
+            property_strings.push(vec![format!("${key}"), value]);
         }
-        ExportFormatOption::Html => todo!(),
+    }
+
+    if let (Some(meta), serde_json::Value::Object(object)) = (meta, &mut json_value) {
+        object.insert("$meta".to_string(), meta);
+    }
+
+    Ok(Some(Output::table(
+        json_value,
+        &["Property", "Value"],
+        property_strings,
+    )?))
+}
+
+fn mentions(note: EnrichedNoteArgs, args: &MentionsArgs) -> CommandResult {
+    let graph = VaultGraph::build(read_notes(&note.vault)?);
+
+    let target_idx = graph
+        .notes
+        .iter()
+        .position(|n| n.path == note.note_path)
+        .ok_or_else(|| ObxError::NoteNotFound(note.note_file.clone()))?;
+    let target = &graph.notes[target_idx];
+
+    let mut terms = vec![target.title()];
+    terms.extend(target.aliases());
+
+    // Prefer the shortest link that still resolves to the note, as Obsidian does
+    let link_target = match graph.resolve(&target.title()) {
+        Some(idx) if idx == target_idx => target.title(),
+        _ => graph.id(target_idx),
+    };
+
+    let found: Vec<Mention> = graph
+        .notes
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx != target_idx)
+        .flat_map(|(_, n)| note_mentions(n, &terms))
+        .collect();
+
+    if !args.link {
+        return Ok(Some(Output::text(&found, format_grouped_mentions(&found))?));
+    }
+
+    if found.is_empty() {
+        return Ok(Some(Output::message("No unlinked mentions found")));
+    }
+
+    let term_is_attended = should_enable_interactivity();
+
+    let selected: Vec<&Mention> = if term_is_attended && !args.yes && !args.dry_run {
+        let items: Vec<String> = found
+            .iter()
+            .map(|m| format!("{}:{} {}", m.file.display(), m.line, m.context.trim()))
+            .collect();
+
+        MultiSelect::new()
+            .with_prompt("Select the mentions to link")
+            .items(&items)
+            .defaults(&vec![true; items.len()])
+            .interact()
+            .context("couldn't prompt user to select mentions")?
+            .into_iter()
+            .map(|idx| &found[idx])
+            .collect()
+    } else {
+        found.iter().collect()
     };
 
-    Ok(Some(formatted))
+    let changes = linked_lines(&selected, &link_target);
+    let diff = changes
+        .iter()
+        .map(|(file, line, old, new)| format!("{}:{line}\n- {old}\n+ {new}", file.display()))
+        .collect::<Vec<String>>()
+        .join("\n\n");
+
+    if args.dry_run {
+        let data: Vec<serde_json::Value> = changes
+            .iter()
+            .map(|(file, line, old, new)| {
+                json!({
+                    "file": file,
+                    "line": line,
+                    "old": old,
+                    "new": new,
+                })
+            })
+            .collect();
+
+        return Ok(Some(Output::text(data, diff)?));
+    }
+
+    if !args.yes {
+        let confirmation = if term_is_attended {
+            // The preview goes with the prompt on stderr, so stdout is only ever the output
+            eprintln!("{diff}\n");
+
+            Confirm::new()
+                .with_prompt(format!("Link {} mentions?", selected.len()))
+                .interact()
+                .context("couldn't prompt user for confirmation to link mentions")?
+        } else {
+            false
+        };
+
+        if !confirmation {
+            anyhow::bail!(ObxError::Aborted);
+        }
+    }
+
+    let mut changed_files: Vec<&PathBuf> = changes.iter().map(|(file, ..)| file).collect();
+    changed_files.dedup();
+
+    for file in &changed_files {
+        let file_path = note.vault.path.join(file);
+        let contents = fs::read_to_string(&file_path)
+            .with_context(|| format!("Could not read note `{}`", file.display()))?;
+
+        let mut lines: Vec<String> = contents.split_inclusive('\n').map(str::to_string).collect();
+
+        for (_, line_number, old, new) in changes.iter().filter(|(f, ..)| f == *file) {
+            if let Some(line) = lines.get_mut(line_number - 1) {
+                *line = line.replacen(old.as_str(), new, 1);
+            }
+        }
+
+        let contents = timestamps::stamp(&note.vault, &lines.concat(), false)?;
+        fs::write(&file_path, contents)
+            .with_context(|| format!("Could not write note `{}`", file.display()))?;
+    }
+
+    Ok(Some(Output::message(format!(
+        "Linked {} mentions in {} notes",
+        selected.len(),
+        changed_files.len()
+    ))))
+}
+
+/// The lines changed by linking the mentions, as `(file, line, old, new)`
+fn linked_lines(mentions: &[&Mention], link_target: &str) -> Vec<(PathBuf, usize, String, String)> {
+    let mut changes: Vec<(PathBuf, usize, String, String)> = vec![];
+
+    for mention in mentions {
+        let same_line = changes
+            .last()
+            .is_some_and(|(file, line, ..)| *file == mention.file && *line == mention.line);
+
+        if !same_line {
+            changes.push((
+                mention.file.clone(),
+                mention.line,
+                mention.context.clone(),
+                String::new(),
+            ));
+        }
+    }
+
+    for (file, line, old, new) in changes.iter_mut() {
+        let ranges: Vec<_> = mentions
+            .iter()
+            .filter(|m| m.file == *file && m.line == *line)
+            .map(|m| m.range.clone())
+            .collect();
+
+        *new = link_mentions(old, &ranges, link_target);
+    }
+
+    changes
+}
+
+/// Mentions grouped by note, with the line each one was found on
+///
+/// ```text
+/// folder/note.md
+///      3 Notes from the standup
+/// ```
+fn format_grouped_mentions(mentions: &[Mention]) -> String {
+    if mentions.is_empty() {
+        return "No unlinked mentions found".to_string();
+    }
+
+    let mut groups: Vec<String> = vec![];
+    let mut current: Option<(&PathBuf, usize)> = None;
+
+    for mention in mentions {
+        if current.map(|(file, _)| file) != Some(&mention.file) {
+            groups.push(mention.file.display().to_string());
+        } else if current.map(|(_, line)| line) == Some(mention.line) {
+            continue;
+        }
+        current = Some((&mention.file, mention.line));
+
+        let group = groups.last_mut().expect("a group should have been pushed");
+        group.push_str(&format!(
+            "\n  {:>4} {}",
+            mention.line,
+            mention.context.trim()
+        ));
+    }
+
+    groups.join("\n\n")
 }
 
 fn maybe_stdin() -> anyhow::Result<Option<String>> {
     match isnt(Stream::Stdin) {
         true => {
             let mut buffer = String::new();
-            io::stdin().read_line(&mut buffer)?;
+            io::stdin().read_to_string(&mut buffer)?;
             Ok(Some(buffer))
         }
         false => Ok(None),
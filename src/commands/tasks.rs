@@ -0,0 +1,526 @@
+use crate::{
+    error::ObxError,
+    output::Output,
+    tasks::{format_new_task, note_tasks, parse_date, status_matches, toggle_task, Task},
+    timestamps,
+    util::{get_current_vault, obsidian_note_uri, resolve_note, CommandResult},
+    vault::{read_notes, tag_matches, VaultNote},
+};
+use anyhow::{anyhow, bail, Context};
+use chrono::{Days, Local, NaiveDate, Utc};
+use clap::{Args, Subcommand};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+#[derive(Args, Debug, Clone)]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(arg_required_else_help = true)]
+pub struct TasksCommand {
+    #[command(subcommand)]
+    command: Option<Subcommands>,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+enum Subcommands {
+    /// List the tasks (`- [ ]` checkboxes) across every note in the vault
+    List(ListArgs),
+
+    /// Toggle a task between done and not done. Completing a recurring task
+    /// (`🔁 every week`) inserts its next occurrence above it
+    Done(DoneArgs),
+
+    /// Append a new task to the end of a note
+    Add(AddArgs),
+
+    /// Export every task with a date as an iCalendar (.ics) feed. Tasks without a date
+    /// of their own use the `due` property of their note
+    Ics(IcsArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+struct ListArgs {
+    /// Only include tasks with a status of todo, done, in-progress, cancelled, open
+    /// (anything not done or cancelled), or a literal status symbol such as `?`
+    #[arg(long, short = 's')]
+    status: Vec<String>,
+
+    /// Only include tasks from notes within this folder
+    #[arg(long)]
+    folder: Option<PathBuf>,
+
+    /// Only include tasks tagged with this tag, either on the task or on the note
+    #[arg(long, short = 't')]
+    tag: Option<String>,
+
+    /// Only include tasks due before this date (YYYY-MM-DD, today, tomorrow or yesterday)
+    #[arg(long, value_parser = parse_date)]
+    due_before: Option<NaiveDate>,
+
+    /// Only include tasks due after this date
+    #[arg(long, value_parser = parse_date)]
+    due_after: Option<NaiveDate>,
+
+    /// Only include tasks scheduled before this date
+    #[arg(long, value_parser = parse_date)]
+    scheduled_before: Option<NaiveDate>,
+
+    /// Only include tasks scheduled after this date
+    #[arg(long, value_parser = parse_date)]
+    scheduled_after: Option<NaiveDate>,
+}
+
+#[derive(Args, Debug, Clone)]
+struct DoneArgs {
+    #[arg(help = "The task to toggle as <note>:<line>, as shown by `tasks list`")]
+    task: String,
+}
+
+#[derive(Args, Debug, Clone)]
+struct AddArgs {
+    #[arg(help = "The path to the note, if the extension is omitted .md will be assumed")]
+    note: String,
+
+    #[arg(help = "The description of the task")]
+    description: String,
+
+    /// The date the task is due (YYYY-MM-DD, today, tomorrow or yesterday)
+    #[arg(long, value_parser = parse_date)]
+    due: Option<NaiveDate>,
+
+    /// The date the task is scheduled for
+    #[arg(long, value_parser = parse_date)]
+    scheduled: Option<NaiveDate>,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum IcsComponent {
+    /// Export tasks as to-dos (VTODO)
+    Todo,
+    /// Export tasks as all-day events (VEVENT), for calendars without to-do support
+    Event,
+}
+
+#[derive(Args, Debug, Clone)]
+struct IcsArgs {
+    /// Write the feed to a file instead of printing it
+    #[arg(long, short = 'o')]
+    output: Option<PathBuf>,
+
+    #[arg(long, default_value = "todo")]
+    component: IcsComponent,
+}
+
+pub fn entry(cmd: &TasksCommand) -> CommandResult {
+    match &cmd.command {
+        Some(Subcommands::List(args)) => list(args),
+        Some(Subcommands::Done(DoneArgs { task })) => done(task),
+        Some(Subcommands::Add(args)) => add(args),
+        Some(Subcommands::Ics(args)) => ics(args),
+        None => bail!("A tasks command is required, see `obx tasks --help`"),
+    }
+}
+
+fn list(args: &ListArgs) -> CommandResult {
+    let vault = get_current_vault()?;
+    let notes = read_notes(&vault)?;
+
+    let mut tasks: Vec<Task> = vec![];
+
+    for note in &notes {
+        if let Some(folder) = &args.folder {
+            if !note.relative_path.starts_with(folder) {
+                continue;
+            }
+        }
+
+        let note_tags = note.property_tags();
+
+        tasks.extend(
+            note_tasks(note)
+                .into_iter()
+                .filter(|task| matches_filters(task, &note_tags, args)),
+        );
+    }
+
+    Ok(Some(Output::text(&tasks, format_grouped_tasks(&tasks))?))
+}
+
+fn matches_filters(task: &Task, note_tags: &[String], args: &ListArgs) -> bool {
+    let status_ok =
+        args.status.is_empty() || args.status.iter().any(|s| status_matches(s, task.symbol));
+
+    let tag_ok = match &args.tag {
+        Some(filter) => task
+            .tags
+            .iter()
+            .chain(note_tags.iter())
+            .any(|tag| tag_matches(tag, filter)),
+        None => true,
+    };
+
+    let is_before = |date: Option<NaiveDate>, limit: Option<NaiveDate>| match limit {
+        Some(limit) => date.is_some_and(|d| d < limit),
+        None => true,
+    };
+
+    let is_after = |date: Option<NaiveDate>, limit: Option<NaiveDate>| match limit {
+        Some(limit) => date.is_some_and(|d| d > limit),
+        None => true,
+    };
+
+    status_ok
+        && tag_ok
+        && is_before(task.due, args.due_before)
+        && is_after(task.due, args.due_after)
+        && is_before(task.scheduled, args.scheduled_before)
+        && is_after(task.scheduled, args.scheduled_after)
+}
+
+/// Format tasks grouped under the note they were found in, e.g.
+///
+/// ```text
+/// folder/note.md
+///      3 [ ] Write the report 📅 2024-01-10
+/// ```
+fn format_grouped_tasks(tasks: &[Task]) -> String {
+    if tasks.is_empty() {
+        return "No tasks found".to_string();
+    }
+
+    let mut groups: Vec<String> = vec![];
+    let mut current_file: Option<&PathBuf> = None;
+
+    for task in tasks {
+        if current_file != Some(&task.file) {
+            groups.push(task.file.display().to_string());
+            current_file = Some(&task.file);
+        }
+
+        let group = groups.last_mut().expect("a group should have been pushed");
+        group.push_str(&format!(
+            "\n  {:>4} [{}] {}",
+            task.line, task.symbol, task.description
+        ));
+    }
+
+    groups.join("\n\n")
+}
+
+fn done(task_id: &str) -> CommandResult {
+    let (note, line_number) = task_id
+        .rsplit_once(':')
+        .and_then(|(note, line)| Some((note, line.parse::<usize>().ok()?)))
+        .ok_or_else(|| {
+            ObxError::Parse(format!("Invalid task `{task_id}`, expected <note>:<line>"))
+        })?;
+
+    let vault = get_current_vault()?;
+    let note_path = resolve_note(note, &vault)?;
+    if !note_path.is_file() {
+        bail!(ObxError::NoteNotFound(note.to_string()));
+    }
+    let note_file = note_path.strip_prefix(&vault.path).unwrap_or(&note_path);
+
+    let contents = fs::read_to_string(&note_path)
+        .with_context(|| format!("Could not read note `{}`", note_file.display()))?;
+
+    let mut lines: Vec<&str> = contents.split_inclusive('\n').collect();
+
+    let Some(line) = line_number.checked_sub(1).and_then(|idx| lines.get(idx)) else {
+        bail!(
+            "Line {line_number} is out of range for note `{}`",
+            note_file.display()
+        );
+    };
+
+    let (text, line_ending) = match line.strip_suffix("\r\n") {
+        Some(text) => (text, "\r\n"),
+        None => match line.strip_suffix('\n') {
+            Some(text) => (text, "\n"),
+            None => (*line, "\n"),
+        },
+    };
+
+    let (replacements, is_done) = toggle_task(text, Local::now().date_naive()).map_err(|e| {
+        anyhow!(
+            "Could not toggle task {}:{line_number}, {e}",
+            note_file.display()
+        )
+    })?;
+
+    let replacement = replacements
+        .iter()
+        .map(|r| format!("{r}{line_ending}"))
+        .collect::<String>();
+    let is_last_line = line_number == lines.len() && !line.ends_with('\n');
+    let replacement = if is_last_line {
+        replacement.trim_end_matches(line_ending).to_string()
+    } else {
+        replacement
+    };
+
+    lines[line_number - 1] = &replacement;
+    let updated = lines.concat();
+    let stamped = timestamps::stamp(&vault, &updated, false)?;
+    fs::write(&note_path, &stamped)
+        .with_context(|| format!("Could not write note `{}`", note_file.display()))?;
+
+    // Adding timestamp properties can push the task further down the note, and replacing
+    // a multi-line property with a single line can pull it back up
+    let line_number =
+        (line_number + stamped.lines().count()).saturating_sub(updated.lines().count());
+
+    let message = match (is_done, replacements.len()) {
+        (true, 1) => format!("Completed task {}:{line_number}", note_file.display()),
+        (true, _) => format!(
+            "Completed task {file}:{completed}, next occurrence added at {file}:{line_number}",
+            file = note_file.display(),
+            completed = line_number + 1,
+        ),
+        (false, _) => format!("Reopened task {}:{line_number}", note_file.display()),
+    };
+
+    Ok(Some(Output::message(message)))
+}
+
+fn add(args: &AddArgs) -> CommandResult {
+    let vault = get_current_vault()?;
+    let note_path = resolve_note(&args.note, &vault)?;
+    let note_file = note_path.strip_prefix(&vault.path).unwrap_or(&note_path);
+
+    let is_new = !note_path.exists();
+    let mut contents = if !is_new {
+        fs::read_to_string(&note_path)
+            .with_context(|| format!("Could not read note `{}`", note_file.display()))?
+    } else {
+        let note_dir = note_path
+            .parent()
+            .context("note_path should have a parent")?;
+        fs::create_dir_all(note_dir)
+            .with_context(|| format!("Could not create directory {}", note_dir.display()))?;
+        String::new()
+    };
+
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+
+    contents.push_str(&format_new_task(
+        &args.description,
+        args.scheduled,
+        args.due,
+    ));
+    contents.push('\n');
+
+    let contents = timestamps::stamp(&vault, &contents, is_new)?;
+    fs::write(&note_path, &contents)
+        .with_context(|| format!("Could not write note `{}`", note_file.display()))?;
+
+    let line_number = contents.lines().count();
+
+    Ok(Some(Output::message(format!(
+        "Added task {}:{line_number}",
+        note_file.display()
+    ))))
+}
+
+fn ics(args: &IcsArgs) -> CommandResult {
+    let vault = get_current_vault()?;
+    let notes = read_notes(&vault)?;
+
+    let dtstamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut seen_uids: HashMap<String, usize> = HashMap::new();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//obx//obsidian-cli//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!(
+            "X-WR-CALNAME:{}",
+            ics_escape(&format!("{} tasks", vault.name))
+        ),
+    ];
+    let mut task_count = 0;
+
+    for note in &notes {
+        let note_due = note_due_date(note);
+
+        for mut task in note_tasks(note) {
+            if task.due.is_none() && task.scheduled.is_none() && task.start.is_none() {
+                task.due = note_due;
+            }
+
+            let Some(date) = task.due.or(task.scheduled).or(task.start) else {
+                continue;
+            };
+
+            // Line numbers change as notes are edited, so the UID is derived from the
+            // note and task text instead, with a counter to separate identical tasks
+            let uid_source = format!("{}/{}/{}", vault.name, task.file.display(), task.summary());
+            let occurrence = seen_uids.entry(uid_source.clone()).or_insert(0);
+            *occurrence += 1;
+            let uid = format!("{:016x}-{}@obx", fnv1a_hash(&uid_source), occurrence);
+
+            let url = obsidian_note_uri(&note.path, vault.name.clone());
+
+            match args.component {
+                IcsComponent::Todo => lines.extend(vtodo(&task, &uid, &url, &dtstamp)),
+                IcsComponent::Event => lines.extend(vevent(&task, date, &uid, &url, &dtstamp)),
+            }
+            task_count += 1;
+        }
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let calendar = lines
+        .iter()
+        .map(|line| ics_fold(line))
+        .collect::<Vec<String>>()
+        .join("\r\n");
+
+    match &args.output {
+        Some(output) => {
+            fs::write(output, format!("{calendar}\r\n"))
+                .with_context(|| format!("Could not write to {}", output.display()))?;
+
+            Ok(Some(Output::message(format!(
+                "Wrote {task_count} tasks to {}",
+                output.display()
+            ))))
+        }
+        None => Ok(Some(Output::document(calendar))),
+    }
+}
+
+/// The `due` property of a note, used as the date of any tasks without one
+fn note_due_date(note: &VaultNote) -> Option<NaiveDate> {
+    let properties = note.properties()?;
+    let due = properties.get("due")?.as_str()?;
+
+    // Accept datetimes as well as dates, e.g. `2024-01-10T09:00`
+    NaiveDate::parse_from_str(due.get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn ics_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+/// Escape a value for use in an iCalendar TEXT property
+fn ics_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold an iCalendar content line so no line is longer than 75 octets, as required by RFC 5545
+fn ics_fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut line_len = 0;
+
+    for c in line.chars() {
+        if line_len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            line_len = 1;
+        }
+
+        folded.push(c);
+        line_len += c.len_utf8();
+    }
+
+    folded
+}
+
+fn vtodo(task: &Task, uid: &str, url: &str, dtstamp: &str) -> Vec<String> {
+    let mut lines = vec![
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{uid}"),
+        format!("DTSTAMP:{dtstamp}"),
+        format!("SUMMARY:{}", ics_escape(&task.summary())),
+        format!("DESCRIPTION:{}", ics_escape(&task.id)),
+        format!("URL:{url}"),
+    ];
+
+    if let Some(start) = task.scheduled.or(task.start) {
+        lines.push(format!("DTSTART;VALUE=DATE:{}", ics_date(start)));
+    }
+
+    if let Some(due) = task.due {
+        lines.push(format!("DUE;VALUE=DATE:{}", ics_date(due)));
+    }
+
+    let status = match task.status {
+        "done" => "COMPLETED",
+        "cancelled" => "CANCELLED",
+        "in-progress" => "IN-PROCESS",
+        _ => "NEEDS-ACTION",
+    };
+    lines.push(format!("STATUS:{status}"));
+
+    if let Some(done) = task.done {
+        lines.push(format!("COMPLETED:{}T000000Z", ics_date(done)));
+    }
+
+    lines.push("END:VTODO".to_string());
+    lines
+}
+
+fn vevent(task: &Task, date: NaiveDate, uid: &str, url: &str, dtstamp: &str) -> Vec<String> {
+    let end = date.checked_add_days(Days::new(1)).unwrap_or(date);
+
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{uid}"),
+        format!("DTSTAMP:{dtstamp}"),
+        format!("SUMMARY:{}", ics_escape(&task.summary())),
+        format!("DESCRIPTION:{}", ics_escape(&task.id)),
+        format!("URL:{url}"),
+        format!("DTSTART;VALUE=DATE:{}", ics_date(date)),
+        format!("DTEND;VALUE=DATE:{}", ics_date(end)),
+    ];
+
+    if task.status == "cancelled" {
+        lines.push("STATUS:CANCELLED".to_string());
+    }
+
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+/// 64-bit FNV-1a, used instead of `DefaultHasher` as its output must be stable
+/// across Rust versions for UIDs to stay the same between exports
+fn fnv1a_hash(input: &str) -> u64 {
+    input.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ics_escape_escapes_special_characters() {
+        assert_eq!(ics_escape("a, b; c\\d\ne"), "a\\, b\\; c\\\\d\\ne");
+    }
+
+    #[test]
+    fn ics_fold_wraps_long_lines() {
+        let line = format!("SUMMARY:{}", "a".repeat(100));
+        let folded = ics_fold(&line);
+        let lines: Vec<&str> = folded.split("\r\n").collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 75);
+        assert_eq!(lines[1], format!(" {}", "a".repeat(33)));
+    }
+
+    #[test]
+    fn ics_fold_does_not_split_characters() {
+        let folded = ics_fold(&"📅".repeat(30));
+
+        assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), "📅".repeat(30));
+    }
+}
@@ -1,12 +1,22 @@
 use crate::{
-    cli_config,
-    util::{get_current_vault, CommandResult},
+    app_settings, cli_config,
+    doctor::{check_vault, settings_issue, Issue, Level},
+    error::ObxError,
+    output::{self, Output},
+    stats::VaultStats,
+    util::{
+        find_current_vault, get_all_vaults, get_current_vault, should_enable_interactivity,
+        with_settings, CommandResult,
+    },
 };
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
+use chrono::Local;
 use clap::{Args, Subcommand};
-use dialoguer::{theme::ColorfulTheme, Select};
-use std::{fs, io, path::PathBuf};
-use tabled::{builder::Builder, settings::Style};
+use dialoguer::{theme::ColorfulTheme, Confirm, Select};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 #[derive(Args, Debug, Clone)]
 #[command(args_conflicts_with_subcommands = true)]
@@ -23,7 +33,7 @@ enum Subcommands {
     Create(CreateArgs),
 
     /// List all vaults
-    List(ListArgs),
+    List,
 
     /// Set a vault as current, to be implicitly used by commands.
     /// A vault can be explicitly provided, or chosen interactively
@@ -34,6 +44,26 @@ enum Subcommands {
 
     /// Print the absolute path to the current vault
     Path,
+
+    /// Remove a vault from the config, the files in it are left untouched
+    Remove(RemoveArgs),
+
+    /// Rename a vault
+    Rename(RenameArgs),
+
+    /// Change the path of a vault that has moved
+    SetPath(SetPathArgs),
+
+    /// Add vaults registered in the Obsidian app, and flag vaults that no longer exist.
+    /// Existing vaults keep their names
+    Sync(SyncArgs),
+
+    /// Print statistics about the notes in a vault
+    Stats(StatsArgs),
+
+    /// Check every vault for problems, such as invalid frontmatter or ambiguous note
+    /// names. Exits with an error if any are serious
+    Doctor,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -46,56 +76,254 @@ struct CreateArgs {
 
     #[arg(long, help = "Explicitly name the vault")]
     name: Option<String>,
+
+    /// Create the directory if it doesn't exist, and set it up as an Obsidian vault
+    #[arg(long)]
+    init: bool,
+
+    /// Add the vault to the Obsidian app's vault switcher
+    #[arg(long)]
+    register: bool,
+
+    /// A folder of starter templates to copy into the new vault
+    #[arg(long, requires = "init", value_name = "DIR")]
+    templates: Option<PathBuf>,
 }
 
 #[derive(Args, Debug, Clone)]
 struct SwitchArgs {
-    #[arg(help = "The name of the vault to switch to")]
+    // A different id so it doesn't clash with the global `--vault` flag
+    #[arg(
+        id = "vault_name",
+        value_name = "VAULT",
+        help = "The name of the vault to switch to"
+    )]
     vault: Option<String>,
 }
 
-#[derive(clap::ValueEnum, Clone, Debug)]
-enum ListFormats {
-    Pretty,
-    Json,
+#[derive(Args, Debug, Clone)]
+struct RemoveArgs {
+    #[arg(
+        id = "vault_name",
+        value_name = "VAULT",
+        help = "The name of the vault to remove"
+    )]
+    vault: String,
+
+    /// Remove the vault without asking for confirmation
+    #[arg(long, short = 'y')]
+    yes: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct RenameArgs {
+    #[arg(
+        id = "vault_name",
+        value_name = "VAULT",
+        help = "The name of the vault to rename"
+    )]
+    vault: String,
+
+    #[arg(help = "The new name for the vault")]
+    new_name: String,
+}
+
+#[derive(Args, Debug, Clone)]
+struct SetPathArgs {
+    #[arg(
+        id = "vault_name",
+        value_name = "VAULT",
+        help = "The name of the vault that moved"
+    )]
+    vault: String,
+
+    #[arg(help = "The new path to the vault")]
+    vault_path: PathBuf,
+}
+
+#[derive(Args, Debug, Clone)]
+struct SyncArgs {
+    /// Show what would change without updating the config
+    #[arg(long)]
+    dry_run: bool,
 }
 
 #[derive(Args, Debug, Clone)]
-struct ListArgs {
-    #[arg(long, short = 'f', default_value = "pretty")]
-    format: ListFormats,
+struct StatsArgs {
+    /// Show stats for every vault in the config
+    #[arg(long)]
+    all_vaults: bool,
+
+    /// How many weeks of activity to show
+    #[arg(long, default_value_t = 8)]
+    weeks: usize,
 }
 
-pub fn entry(cmd: &VaultsCommand) -> anyhow::Result<Option<String>> {
+pub fn entry(cmd: &VaultsCommand) -> CommandResult {
     match &cmd.command {
-        Some(Subcommands::Create(CreateArgs {
-            vault_path: vault,
-            name,
-        })) => create(vault, name.clone()),
-        Some(Subcommands::List(ListArgs { format })) => list(format),
+        Some(Subcommands::Create(args)) => create(args),
+        Some(Subcommands::List) => list(),
         Some(Subcommands::Switch(SwitchArgs { vault })) => switch(vault),
         Some(Subcommands::Current) => current(),
         Some(Subcommands::Path) => path(),
-        None => todo!(),
+        Some(Subcommands::Remove(RemoveArgs { vault, yes })) => remove(vault, *yes),
+        Some(Subcommands::Rename(RenameArgs { vault, new_name })) => rename(vault, new_name),
+        Some(Subcommands::SetPath(SetPathArgs { vault, vault_path })) => {
+            set_path(vault, vault_path)
+        }
+        Some(Subcommands::Sync(SyncArgs { dry_run })) => sync(*dry_run),
+        Some(Subcommands::Stats(args)) => stats(args),
+        Some(Subcommands::Doctor) => doctor(),
+        None => bail!("A vaults command is required, see `obx vaults --help`"),
+    }
+}
+
+fn create(args: &CreateArgs) -> CommandResult {
+    let vault_path = &args.vault_path;
+    let vault_name = args
+        .name
+        .clone()
+        .unwrap_or_else(|| cli_config::vault_name_from_path(vault_path));
+
+    let mut config = cli_config::read_persisted()?;
+
+    if config.vaults.iter().any(|v| v.name == vault_name) {
+        return Err(anyhow!(
+            "Could not create vault `{vault_name}`, a vault with that name already exists"
+        ));
+    }
+
+    if args.init {
+        fs::create_dir_all(vault_path)
+            .with_context(|| format!("Could not create directory `{}`", vault_path.display()))?;
+    }
+
+    let vault = cli_config::Vault {
+        name: vault_name.clone(),
+        path: resolve_vault_dir(vault_path, "create vault")?,
+        ..Default::default()
+    };
+    let mut messages = vec![format!("Created vault {vault_name}")];
+
+    if args.init {
+        // A folder that already has a `.obx.yml` can choose its own templates folder
+        let templates_folder = with_settings(vault.clone(), &config.defaults)?
+            .settings
+            .templates_folder
+            .unwrap_or_else(|| PathBuf::from("templates"));
+
+        scaffold_vault(&vault.path, args.templates.as_deref(), &templates_folder)?;
+    }
+
+    if args.register {
+        if app_settings::register(&vault.path)? {
+            messages.push(format!("Registered {vault_name} with Obsidian"));
+        } else {
+            messages.push(format!("{vault_name} is already registered with Obsidian"));
+        }
+    }
+
+    config.current_vault = vault_name;
+    config.vaults.push(vault);
+
+    cli_config::write(&config)?;
+
+    Ok(Some(Output::message(messages.join("\n"))))
+}
+
+/// The core plugins a new vault starts with, matching Obsidian's defaults
+const DEFAULT_CORE_PLUGINS: [&str; 17] = [
+    "file-explorer",
+    "global-search",
+    "switcher",
+    "graph",
+    "backlink",
+    "outgoing-link",
+    "tag-pane",
+    "page-preview",
+    "daily-notes",
+    "templates",
+    "note-composer",
+    "command-palette",
+    "editor-status",
+    "bookmarks",
+    "outline",
+    "word-count",
+    "file-recovery",
+];
+
+/// Set up `.obsidian/` so the app opens the folder as a vault, copying in any starter
+/// templates. Files that already exist are left alone
+fn scaffold_vault(
+    vault_path: &Path,
+    templates: Option<&Path>,
+    templates_folder: &Path,
+) -> anyhow::Result<()> {
+    let obsidian_dir = vault_path.join(".obsidian");
+    fs::create_dir_all(&obsidian_dir)
+        .with_context(|| format!("Could not create `{}`", obsidian_dir.display()))?;
+
+    let mut files = vec![
+        ("app.json", "{}".to_string()),
+        ("appearance.json", "{}".to_string()),
+        (
+            "core-plugins.json",
+            serde_json::to_string_pretty(&DEFAULT_CORE_PLUGINS)?,
+        ),
+    ];
+
+    if let Some(templates) = templates {
+        let destination = vault_path.join(templates_folder);
+        copy_dir(templates, &destination).with_context(|| {
+            format!(
+                "Could not copy templates from `{}` to `{}`",
+                templates.display(),
+                destination.display()
+            )
+        })?;
+
+        // Point the templates core plugin at the copied templates
+        let templates_settings = serde_json::json!({ "folder": templates_folder });
+        files.push((
+            "templates.json",
+            serde_json::to_string_pretty(&templates_settings)?,
+        ));
+    }
+
+    for (file, contents) in files {
+        let file_path = obsidian_dir.join(file);
+        if !file_path.exists() {
+            fs::write(&file_path, contents)
+                .with_context(|| format!("Could not write `{}`", file_path.display()))?;
+        }
     }
+
+    Ok(())
 }
 
-fn create(vault_path: &PathBuf, vault_name_override: Option<String>) -> CommandResult {
-    let vault_name = vault_name_override.unwrap_or_else(|| {
-        vault_path
-            .components()
-            .last()
-            .unwrap()
-            .as_os_str()
-            .to_str()
-            .unwrap()
-            .to_string()
-    });
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let destination = to.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &destination)?;
+        } else if !destination.exists() {
+            fs::copy(entry.path(), &destination)?;
+        }
+    }
+
+    Ok(())
+}
 
+/// The absolute path to a vault directory, `action` describes what it's for in errors
+fn resolve_vault_dir(vault_path: &PathBuf, action: &str) -> anyhow::Result<PathBuf> {
     let resolved_path = fs::canonicalize(vault_path).map_err(|err| {
         if err.kind() == io::ErrorKind::NotFound {
             anyhow!(
-                "Could not create vault at path `{}`, directory not found",
+                "Could not {action} at path `{}`, directory not found",
                 vault_path.display()
             )
         } else {
@@ -105,73 +333,59 @@ fn create(vault_path: &PathBuf, vault_name_override: Option<String>) -> CommandR
 
     if !resolved_path.is_dir() {
         return Err(anyhow!(
-            "Could not create vault at path `{}`, path must be a directory",
+            "Could not {action} at path `{}`, path must be a directory",
             vault_path.display()
         ));
     }
 
-    let mut config = cli_config::read()?;
-
-    config.current_vault = vault_name.clone();
-    config.vaults.push(cli_config::Vault {
-        name: vault_name.clone(),
-        path: resolved_path,
-    });
-
-    let _ = cli_config::write(&config);
-
-    Ok(Some(format!("Created vault {vault_name}")))
+    Ok(resolved_path)
 }
 
-fn list(list_format: &ListFormats) -> CommandResult {
-    let config = cli_config::read()?;
-
-    let formatted = match list_format {
-        &ListFormats::Json => serde_json::to_string(&config.vaults)?,
-        ListFormats::Pretty => format_vault_table(&config),
-    };
-
-    Ok(Some(formatted))
+/// The position of a vault in the config
+fn find_vault(config: &cli_config::Config, vault_name: &str) -> anyhow::Result<usize> {
+    config
+        .vaults
+        .iter()
+        .position(|v| v.name == vault_name)
+        .ok_or_else(|| ObxError::VaultNotFound(vault_name.to_string()).into())
 }
 
-pub fn format_vault_table(config: &cli_config::Config) -> String {
-    let mut builder = Builder::new();
-
-    for v in &config.vaults {
-        builder.push_record([v.name.clone(), v.path.display().to_string()]);
-    }
-    builder.insert_record(0, vec!["Name", "Path"]);
+fn list() -> CommandResult {
+    let config = cli_config::read()?;
 
-    let mut table = builder.build();
-    table.with(Style::sharp());
+    let rows = config
+        .vaults
+        .iter()
+        .map(|v| vec![v.name.clone(), v.path.display().to_string()])
+        .collect();
 
-    format!("{table}")
+    Ok(Some(Output::table(
+        &config.vaults,
+        &["Name", "Path"],
+        rows,
+    )?))
 }
 
 fn switch(vault_name_arg: &Option<String>) -> CommandResult {
-    let mut config = cli_config::read()?;
+    let mut config = cli_config::read_persisted()?;
 
     let vault_name: String = match vault_name_arg {
         Some(s) => s.to_string(),
-        None => interactive_switch(&config, "Select a vault"),
+        None => interactive_switch(&config, "Select a vault")?,
     };
 
-    config
-        .vaults
-        .iter()
-        .find(|v| v.name == vault_name)
-        .with_context(|| {
-            format!("Could not switch to vault `{vault_name}`, vault doesn't exist")
-        })?;
+    find_vault(&config, &vault_name)?;
 
     config.current_vault = vault_name.to_string();
 
-    let _ = cli_config::write(&config);
+    cli_config::write(&config)?;
 
-    Ok(Some(format!("Switched to vault {vault_name}")))
+    Ok(Some(Output::message(format!(
+        "Switched to vault {vault_name}"
+    ))))
 }
 
-pub fn interactive_switch(config: &cli_config::Config, message: &str) -> String {
+pub fn interactive_switch(config: &cli_config::Config, message: &str) -> anyhow::Result<String> {
     // Construct a list of vaults in the format `vault (path)`
     let vaults: Vec<String> = config
         .vaults
@@ -183,21 +397,16 @@ pub fn interactive_switch(config: &cli_config::Config, message: &str) -> String
         .with_prompt(message)
         .items(&vaults)
         .interact()
-        .unwrap();
+        .context("couldn't prompt user to select a vault")?;
 
     let selected_vault = &config.vaults[selection];
 
-    selected_vault.name.to_string()
+    Ok(selected_vault.name.to_string())
 }
 
 fn current() -> CommandResult {
-    let config = cli_config::read()?;
-
-    let found_vault = config
-        .vaults
-        .iter()
-        .find(|v| v.name == config.current_vault)
-        .context("Expected to find vault matching current_vault in config")?;
+    // The same vault every other command would use, so `--vault` and the cwd count too
+    let found_vault = get_current_vault()?;
 
     let out = format!(
         "Current vault is `{name}` at path `{path}`",
@@ -205,12 +414,400 @@ fn current() -> CommandResult {
         path = found_vault.path.display()
     );
 
-    Ok(Some(out))
+    Ok(Some(Output::text(&found_vault, out)?))
 }
 
 fn path() -> CommandResult {
-    let vault = get_current_vault(None)?;
-    let vault_path = vault.path.to_str().unwrap().to_string();
+    let vault = get_current_vault()?;
+    let vault_path = vault.path.display().to_string();
+
+    Ok(Some(Output::text(
+        serde_json::json!({ "path": vault_path }),
+        vault_path,
+    )?))
+}
+
+fn remove(vault_name: &str, yes: bool) -> CommandResult {
+    let mut config = cli_config::read_persisted()?;
+    let idx = find_vault(&config, vault_name)?;
+
+    if config.current_vault == vault_name {
+        return Err(anyhow!(
+            "Could not remove vault `{vault_name}`, it's the current vault. Switch to another vault first"
+        ));
+    }
+
+    if !yes {
+        let confirmation = should_enable_interactivity()
+            && Confirm::new()
+                .with_prompt(format!(
+                    "Remove vault `{vault_name}` at path `{}`?",
+                    config.vaults[idx].path.display()
+                ))
+                .interact()
+                .context("couldn't prompt user for confirmation to remove vault")?;
+
+        if !confirmation {
+            bail!(ObxError::Aborted);
+        }
+    }
+
+    config.vaults.remove(idx);
+    cli_config::write(&config)?;
+
+    Ok(Some(Output::message(format!("Removed vault {vault_name}"))))
+}
+
+fn rename(vault_name: &str, new_name: &str) -> CommandResult {
+    let mut config = cli_config::read_persisted()?;
+    let idx = find_vault(&config, vault_name)?;
+
+    if config.vaults.iter().any(|v| v.name == new_name) {
+        return Err(anyhow!(
+            "Could not rename vault `{vault_name}`, a vault named `{new_name}` already exists"
+        ));
+    }
+
+    config.vaults[idx].name = new_name.to_string();
+    if config.current_vault == vault_name {
+        config.current_vault = new_name.to_string();
+    }
+
+    cli_config::write(&config)?;
+
+    Ok(Some(Output::message(format!(
+        "Renamed vault {vault_name} to {new_name}"
+    ))))
+}
+
+fn set_path(vault_name: &str, vault_path: &PathBuf) -> CommandResult {
+    let mut config = cli_config::read_persisted()?;
+    let idx = find_vault(&config, vault_name)?;
+
+    let resolved_path = resolve_vault_dir(vault_path, &format!("move vault `{vault_name}`"))?;
+    config.vaults[idx].path = resolved_path.clone();
+
+    cli_config::write(&config)?;
+
+    Ok(Some(Output::message(format!(
+        "Moved vault {vault_name} to {}",
+        resolved_path.display()
+    ))))
+}
+
+/// Whether two paths point at the same place, Obsidian doesn't resolve symlinks but
+/// obx does
+fn same_path(a: &Path, b: &Path) -> bool {
+    let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    a == b || canonical(a) == canonical(b)
+}
+
+/// `name`, with a number added if a vault already has it
+fn unique_vault_name(config: &cli_config::Config, name: String) -> String {
+    let taken = |candidate: &str| config.vaults.iter().any(|v| v.name == candidate);
+    if !taken(&name) {
+        return name;
+    }
+
+    (2..)
+        .map(|n| format!("{name}-{n}"))
+        .find(|candidate| !taken(candidate))
+        .expect("there's always an unused name")
+}
+
+fn sync(dry_run: bool) -> CommandResult {
+    if !cli_config::exists() {
+        return Err(anyhow!(
+            "No config file found, run `obx init` to create one"
+        ));
+    }
+
+    let settings = app_settings::read()?;
+    let mut config = cli_config::read_persisted()?;
+    let mut changes: Vec<String> = vec![];
+
+    let verb = |done: &'static str, planned: &'static str| if dry_run { planned } else { done };
+
+    for app_vault in settings.recent_vaults() {
+        if config
+            .vaults
+            .iter()
+            .any(|v| same_path(&v.path, &app_vault.path))
+        {
+            continue;
+        }
+
+        let name = unique_vault_name(&config, cli_config::vault_name_from_path(&app_vault.path));
+        changes.push(format!(
+            "{} vault {name} at path `{}`",
+            verb("Added", "Would add"),
+            app_vault.path.display()
+        ));
+        config.vaults.push(cli_config::Vault {
+            name,
+            path: app_vault.path.clone(),
+            ..Default::default()
+        });
+    }
+
+    // When the current vault is gone, switch to the one that's open in Obsidian
+    let current_exists = config
+        .vaults
+        .iter()
+        .any(|v| v.name == config.current_vault && v.path.is_dir());
+
+    if !current_exists {
+        let open_vault = settings.open_vault().and_then(|app_vault| {
+            config
+                .vaults
+                .iter()
+                .find(|v| same_path(&v.path, &app_vault.path) && v.path.is_dir())
+        });
+
+        if let Some(open_vault) = open_vault {
+            changes.push(format!(
+                "{} vault {}, it's open in Obsidian",
+                verb("Switched to", "Would switch to"),
+                open_vault.name
+            ));
+            config.current_vault = open_vault.name.clone();
+        }
+    }
+
+    let missing: Vec<String> = config
+        .vaults
+        .iter()
+        .filter(|v| !v.path.is_dir())
+        .map(|v| {
+            format!(
+                "Vault {} is missing, `{}` doesn't exist. Use `obx vaults set-path` or `obx vaults remove` to fix it",
+                v.name,
+                v.path.display()
+            )
+        })
+        .collect();
+
+    if changes.is_empty() {
+        changes.push("Vaults are already in sync with Obsidian".to_string());
+    } else if !dry_run {
+        cli_config::write(&config)?;
+    }
+
+    Ok(Some(Output::message(
+        [changes, missing].concat().join("\n"),
+    )))
+}
+
+fn stats(args: &StatsArgs) -> CommandResult {
+    if args.all_vaults && cli_config::vault_override().is_some() {
+        return Err(anyhow!("Use either --vault or --all-vaults, not both"));
+    }
+
+    let vaults = if args.all_vaults {
+        // One vault that's been moved or deleted shouldn't hide the stats for the rest
+        get_all_vaults()?
+            .into_iter()
+            .filter(|vault| {
+                let exists = vault.path.is_dir();
+                if !exists {
+                    eprintln!(
+                        "Warning: Skipping vault {}, `{}` doesn't exist",
+                        vault.name,
+                        vault.path.display()
+                    );
+                }
+                exists
+            })
+            .collect()
+    } else {
+        vec![get_current_vault()?]
+    };
+
+    let today = Local::now().date_naive();
+    let stats = vaults
+        .iter()
+        .map(|vault| VaultStats::collect(vault, args.weeks, today))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let tables = stats
+        .iter()
+        .map(format_stats_tables)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let output = match args.all_vaults {
+        true => Output::text(&stats, tables)?,
+        false => Output::text(&stats[0], tables)?,
+    };
+
+    Ok(Some(output))
+}
+
+/// A summary table for the vault, followed by its weekly activity
+fn format_stats_tables(stats: &VaultStats) -> String {
+    let join_counts = |counts: Vec<String>| {
+        if counts.is_empty() {
+            "-".to_string()
+        } else {
+            counts.join(", ")
+        }
+    };
+
+    let summary = [
+        ("Vault", stats.vault.clone()),
+        ("Notes", stats.notes.to_string()),
+        ("Words", stats.words.to_string()),
+        (
+            "Attachments",
+            join_counts(
+                stats
+                    .attachments
+                    .iter()
+                    .map(|(ext, count)| format!("{ext}: {count}"))
+                    .collect(),
+            ),
+        ),
+        (
+            "Folders",
+            join_counts(
+                stats
+                    .folders
+                    .iter()
+                    .map(|(folder, count)| format!("{folder}: {count}"))
+                    .collect(),
+            ),
+        ),
+        (
+            "Top tags",
+            join_counts(
+                stats
+                    .top_tags
+                    .iter()
+                    .map(|tag| format!("#{} ({})", tag.name, tag.count))
+                    .collect(),
+            ),
+        ),
+        (
+            "Top properties",
+            join_counts(
+                stats
+                    .top_properties
+                    .iter()
+                    .map(|property| format!("{} ({})", property.name, property.count))
+                    .collect(),
+            ),
+        ),
+        ("Links", stats.links.to_string()),
+        ("Links per note", format!("{:.2}", stats.link_density)),
+        ("Orphans", stats.orphans.to_string()),
+    ];
+
+    let summary_rows: Vec<Vec<String>> = summary
+        .into_iter()
+        .map(|(metric, value)| vec![metric.to_string(), value])
+        .collect();
+
+    let activity_rows: Vec<Vec<String>> = stats
+        .activity
+        .iter()
+        .map(|week| {
+            vec![
+                week.week.clone(),
+                week.created.to_string(),
+                week.modified.to_string(),
+            ]
+        })
+        .collect();
+
+    format!(
+        "{}\n{}",
+        output::table(&["Metric", "Value"], &summary_rows),
+        output::table(&["Week", "Created", "Modified"], &activity_rows)
+    )
+}
+
+fn plural(count: usize, word: &str) -> String {
+    match count {
+        1 => format!("{count} {word}"),
+        _ => format!("{count} {word}s"),
+    }
+}
+
+fn format_issue(issue: &Issue) -> String {
+    let location = match (&issue.path, issue.line) {
+        (Some(path), Some(line)) => format!("{}:{line}: ", path.display()),
+        (Some(path), None) => format!("{}: ", path.display()),
+        _ => String::new(),
+    };
+
+    format!(
+        "{}: [{}] {location}{}",
+        issue.level.as_str(),
+        issue.vault,
+        issue.message
+    )
+}
+
+fn doctor() -> CommandResult {
+    // Check every vault, unless one was asked for. Settings are applied to each vault
+    // separately, so a broken `.obx.yml` is reported rather than stopping the checks
+    let (vaults, defaults) = match cli_config::vault_override() {
+        Some(_) => {
+            let (vault, defaults) = find_current_vault()?;
+            (vec![vault], defaults)
+        }
+        None => {
+            let config = cli_config::read()?;
+            (config.vaults, config.defaults)
+        }
+    };
+
+    let mut issues: Vec<Issue> = vec![];
+    for vault in &vaults {
+        let vault = match with_settings(vault.clone(), &defaults) {
+            Ok(vault) => vault,
+            Err(e) => {
+                issues.push(settings_issue(vault, &e));
+
+                // Still check the vault, with the settings from the config
+                let mut vault = vault.clone();
+                vault.settings = vault.settings.merged_with(&defaults);
+                vault
+            }
+        };
+
+        issues.extend(check_vault(&vault));
+    }
+
+    let errors = issues.iter().filter(|i| i.level == Level::Error).count();
+    let warnings = issues.len() - errors;
+
+    let summary = format!(
+        "Found {} and {} in {}",
+        plural(errors, "error"),
+        plural(warnings, "warning"),
+        plural(vaults.len(), "vault")
+    );
+
+    let mut lines: Vec<String> = issues.iter().map(format_issue).collect();
+    if issues.is_empty() {
+        lines.push(format!(
+            "No problems found in {}",
+            plural(vaults.len(), "vault")
+        ));
+    } else if errors == 0 {
+        lines.push(summary.clone());
+    }
+
+    let report = Output::text(&issues, lines.join("\n"))?;
+
+    // The report still goes to stdout, with the summary as the error
+    if errors > 0 {
+        bail!(output::FailedWithOutput {
+            output: report,
+            message: summary,
+        });
+    }
 
-    Ok(Some(vault_path))
+    Ok(Some(report))
 }
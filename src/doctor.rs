@@ -0,0 +1,301 @@
+use crate::{
+    cli_config::{Vault, LOCAL_CONFIG_FILE},
+    formats::split_frontmatter,
+    vault::{is_note, list_files, normalize_name},
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        }
+    }
+}
+
+/// A problem found in a vault
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub vault: String,
+    pub level: Level,
+    /// A short name for the check that found it, e.g. `frontmatter`
+    pub check: &'static str,
+    /// The file, relative to the vault, if it's about a single file
+    pub path: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Parts of file names that sync tools give conflicting copies of a file
+const CONFLICT_MARKERS: [&str; 3] = ["sync-conflict", "conflicted copy", "(conflict"];
+
+/// An error for a vault whose `.obx.yml` couldn't be read or isn't valid
+pub fn settings_issue(vault: &Vault, error: &anyhow::Error) -> Issue {
+    Issue {
+        vault: vault.name.clone(),
+        level: Level::Error,
+        check: "settings",
+        path: Some(PathBuf::from(LOCAL_CONFIG_FILE)),
+        line: None,
+        message: format!("Invalid vault settings, {}", error.root_cause()),
+    }
+}
+
+/// Run every check against a vault
+pub fn check_vault(vault: &Vault) -> Vec<Issue> {
+    let issue = |level, check, path: Option<&Path>, line, message: String| Issue {
+        vault: vault.name.clone(),
+        level,
+        check,
+        path: path.map(Path::to_path_buf),
+        line,
+        message,
+    };
+
+    if !vault.path.exists() {
+        let message = format!("Vault path `{}` doesn't exist", vault.path.display());
+        return vec![issue(Level::Error, "path", None, None, message)];
+    }
+    if !vault.path.is_dir() {
+        let message = format!("Vault path `{}` isn't a directory", vault.path.display());
+        return vec![issue(Level::Error, "path", None, None, message)];
+    }
+
+    let mut issues = vec![];
+
+    if !vault.path.join(".obsidian").is_dir() {
+        issues.push(issue(
+            Level::Warning,
+            "obsidian",
+            None,
+            None,
+            "No `.obsidian` folder, this folder hasn't been opened as a vault in Obsidian"
+                .to_string(),
+        ));
+    }
+
+    let files = match list_files(vault) {
+        Ok(files) => files,
+        Err(e) => {
+            issues.push(issue(Level::Error, "path", None, None, e.to_string()));
+            return issues;
+        }
+    };
+
+    let relative_paths: Vec<PathBuf> = files
+        .iter()
+        .map(|path| path.strip_prefix(&vault.path).unwrap_or(path).to_path_buf())
+        .collect();
+
+    for (path, relative_path) in files.iter().zip(&relative_paths) {
+        let file_name = relative_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if CONFLICT_MARKERS
+            .iter()
+            .any(|marker| file_name.contains(marker))
+        {
+            issues.push(issue(
+                Level::Warning,
+                "sync-conflict",
+                Some(relative_path.as_path()),
+                None,
+                "Looks like a conflicting copy left by a sync tool".to_string(),
+            ));
+        }
+
+        if !is_note(path) {
+            continue;
+        }
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                let message = format!("Could not read note, {e}");
+                issues.push(issue(
+                    Level::Error,
+                    "read",
+                    Some(relative_path.as_path()),
+                    None,
+                    message,
+                ));
+                continue;
+            }
+        };
+
+        if contents.is_empty() {
+            issues.push(issue(
+                Level::Warning,
+                "empty",
+                Some(relative_path.as_path()),
+                None,
+                "Note is empty".to_string(),
+            ));
+        }
+
+        if let Some((line, message)) = frontmatter_error(&contents) {
+            issues.push(issue(
+                Level::Error,
+                "frontmatter",
+                Some(relative_path.as_path()),
+                line,
+                message,
+            ));
+        }
+    }
+
+    for group in case_collisions(&relative_paths) {
+        issues.push(issue(
+            Level::Warning,
+            "case-collision",
+            None,
+            None,
+            format!(
+                "Files differ only by case, which breaks on case-insensitive file systems: {}",
+                display_paths(&group)
+            ),
+        ));
+    }
+
+    for (name, group) in duplicate_basenames(&relative_paths) {
+        issues.push(issue(
+            Level::Warning,
+            "duplicate-name",
+            None,
+            None,
+            format!(
+                "Links to `{name}` are ambiguous, it matches {}",
+                display_paths(&group)
+            ),
+        ));
+    }
+
+    issues
+}
+
+fn display_paths(paths: &[&PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The line the frontmatter's YAML error is on, counting from the start of the note,
+/// and the error itself. `None` when the frontmatter is valid or there isn't any
+fn frontmatter_error(contents: &str) -> Option<(Option<usize>, String)> {
+    let starts_frontmatter = contents.starts_with("---\n") || contents.starts_with("---\r\n");
+    if !starts_frontmatter {
+        return None;
+    }
+
+    let Some((frontmatter, _)) = split_frontmatter(contents) else {
+        return Some((
+            Some(1),
+            "Frontmatter is never closed with `---`".to_string(),
+        ));
+    };
+
+    let error = serde_yaml::from_str::<serde_yaml::Value>(frontmatter).err()?;
+
+    // The frontmatter starts on the line after the opening `---`
+    let line = error.location().map(|location| location.line() + 1);
+    let message = format!("Invalid frontmatter, {error}");
+
+    Some((line, message))
+}
+
+/// Groups of files whose paths are the same apart from case
+fn case_collisions(relative_paths: &[PathBuf]) -> Vec<Vec<&PathBuf>> {
+    let mut by_path: BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+    for path in relative_paths {
+        by_path
+            .entry(path.to_string_lossy().to_lowercase())
+            .or_default()
+            .push(path);
+    }
+
+    by_path
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+/// Notes in different folders that share a name, so a `[[name]]` link could mean any of them
+fn duplicate_basenames(relative_paths: &[PathBuf]) -> Vec<(String, Vec<&PathBuf>)> {
+    let mut by_name: BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+    for path in relative_paths.iter().filter(|path| is_note(path)) {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        by_name.entry(normalize_name(&name)).or_default().push(path);
+    }
+
+    by_name
+        .into_iter()
+        .filter(|(_, group)| {
+            // Paths that only differ by case are reported as case collisions instead
+            let mut paths: Vec<String> = group
+                .iter()
+                .map(|path| normalize_name(&path.to_string_lossy()))
+                .collect();
+            paths.sort();
+            paths.dedup();
+            paths.len() > 1
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("---\ntitle: ok\n---\nbody", None ; "valid")]
+    #[test_case("no frontmatter", None ; "no frontmatter")]
+    #[test_case("---\ntitle: ok\nbody", Some(Some(1)) ; "never closed")]
+    #[test_case("---\ntitle: ok\n  bad: indent\n---\n", Some(Some(3)) ; "bad indent")]
+    fn frontmatter_errors_include_the_line(contents: &str, expected: Option<Option<usize>>) {
+        assert_eq!(frontmatter_error(contents).map(|(line, _)| line), expected);
+    }
+
+    #[test]
+    fn finds_case_collisions() {
+        let paths = ["Note.md", "note.md", "other.md"].map(PathBuf::from);
+
+        assert_eq!(case_collisions(&paths), vec![vec![&paths[0], &paths[1]]]);
+    }
+
+    #[test]
+    fn finds_duplicate_basenames_in_different_folders() {
+        let paths = [
+            "ideas.md",
+            "archive/Ideas.md",
+            "Note.md",
+            "note.md",
+            "image.png",
+        ]
+        .map(PathBuf::from);
+
+        assert_eq!(
+            duplicate_basenames(&paths),
+            vec![("ideas".to_string(), vec![&paths[0], &paths[1]])]
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{
+    cli_config,
+    error::ObxError,
+    vault::{heading, is_code_fence},
+};
+use anyhow::{bail, Context};
+use std::{
+    env,
+    path::Path,
+    process::{self, ExitStatus},
+};
+
+/// A position within a note to open the editor at, from `note#Heading`, `note#^block`
+/// or `note:42`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anchor {
+    Heading(String),
+    Block(String),
+    Line(usize),
+}
+
+/// Split an anchor off the end of a note argument, returning the note and the anchor
+pub fn split_anchor(note: &str) -> (&str, Option<Anchor>) {
+    if let Some((name, anchor)) = note.split_once('#') {
+        // Nested headings like `note#Parent#Child` jump to the last heading
+        let anchor = anchor.rsplit('#').next().unwrap_or(anchor);
+
+        return match anchor.strip_prefix('^') {
+            Some(block) => (name, Some(Anchor::Block(block.to_string()))),
+            None => (name, Some(Anchor::Heading(anchor.to_string()))),
+        };
+    }
+
+    match note.rsplit_once(':') {
+        Some((name, line)) if !line.is_empty() && line.chars().all(|c| c.is_ascii_digit()) => {
+            match line.parse() {
+                Ok(line) => (name, Some(Anchor::Line(line))),
+                Err(_) => (note, None),
+            }
+        }
+        _ => (note, None),
+    }
+}
+
+fn normalize_heading(heading: &str) -> String {
+    heading
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The 1-based line number an anchor points to within the contents of a note, or `None`
+/// when the heading or block can't be found or the line is past the end of the note
+pub fn anchor_line(contents: &str, anchor: &Anchor) -> Option<usize> {
+    let mut in_code_block = false;
+
+    for (idx, line) in contents.lines().enumerate() {
+        if is_code_fence(line) {
+            in_code_block = !in_code_block;
+            continue;
+        }
+
+        if in_code_block {
+            continue;
+        }
+
+        let matches = match anchor {
+            Anchor::Line(_) => false,
+            Anchor::Heading(target) => heading(line)
+                .is_some_and(|(_, text)| normalize_heading(text) == normalize_heading(target)),
+            // Obsidian only treats `^id` as a block ID when it follows whitespace
+            Anchor::Block(block) => line
+                .trim_end()
+                .strip_suffix(&format!("^{block}"))
+                .is_some_and(|before| before.is_empty() || before.ends_with(char::is_whitespace)),
+        };
+
+        if matches {
+            return Some(idx + 1);
+        }
+    }
+
+    match anchor {
+        // An empty note still has a line for the editor to open at
+        Anchor::Line(line) if (1..=contents.lines().count().max(1)).contains(line) => Some(*line),
+        _ => None,
+    }
+}
+
+/// Split a command such as `code --wait "my file"` into its arguments, respecting
+/// quotes and backslash escapes the way a shell would
+pub fn split_command(command: &str) -> anyhow::Result<Vec<String>> {
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+                in_arg = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        bail!("Unterminated quote in `{command}`");
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    Ok(args)
+}
+
+/// The arguments needed to open `file` at `line` in the given editor program
+fn jump_args(program: &str, file: &str, line: usize, line_pattern: Option<&str>) -> Vec<String> {
+    let name = Path::new(program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match name.as_str() {
+        "vi" | "vim" | "nvim" | "gvim" | "nano" | "emacs" | "emacsclient" | "micro" | "kak" => {
+            vec![format!("+{line}"), file.to_string()]
+        }
+        "code" | "code-insiders" | "codium" | "cursor" => {
+            vec!["-g".to_string(), format!("{file}:{line}")]
+        }
+        "subl" | "hx" | "zed" => vec![format!("{file}:{line}")],
+        _ => match line_pattern {
+            Some(pattern) => pattern
+                .split_whitespace()
+                .map(|arg| {
+                    arg.replace("{line}", &line.to_string())
+                        .replace("{file}", file)
+                })
+                .collect(),
+            None => vec![file.to_string()],
+        },
+    }
+}
+
+/// Build the command to open `file` in the user's `$EDITOR`, at `line` if given
+pub fn editor_command(
+    editor: &str,
+    file: &Path,
+    line: Option<usize>,
+    line_pattern: Option<&str>,
+) -> anyhow::Result<process::Command> {
+    let mut editor_args =
+        split_command(editor).map_err(|e| ObxError::EditorFailed(e.to_string()))?;
+    if editor_args.is_empty() {
+        bail!(ObxError::EditorFailed("$EDITOR is empty".into()));
+    }
+    let program = editor_args.remove(0);
+
+    let file = file.to_string_lossy();
+    let file_args = match line {
+        Some(line) => jump_args(&program, &file, line, line_pattern),
+        None => vec![file.to_string()],
+    };
+
+    let mut command = process::Command::new(program);
+    command.args(editor_args).args(file_args);
+
+    Ok(command)
+}
+
+/// Open a file in `editor`, falling back to `$EDITOR`, and wait for it to close
+pub fn open_in_editor(
+    file: &Path,
+    line: Option<usize>,
+    editor: Option<&str>,
+) -> anyhow::Result<ExitStatus> {
+    let editor = match editor {
+        Some(editor) => editor.to_string(),
+        None => env::var("EDITOR").context(ObxError::EditorFailed("$EDITOR not found".into()))?,
+    };
+
+    // A broken config shouldn't stop the editor opening, e.g. to fix the config itself
+    let line_pattern = match line {
+        Some(_) => cli_config::read()
+            .ok()
+            .and_then(|config| config.editor_line_pattern),
+        None => None,
+    };
+
+    editor_command(&editor, file, line, line_pattern.as_deref())?
+        .status()
+        .with_context(|| ObxError::EditorFailed(format!("failed to execute $EDITOR={editor}")))
+}
+
+/// The number of lines added and removed between two versions of a file
+pub fn line_changes(before: &str, after: &str) -> (usize, usize) {
+    let before: Vec<&str> = before.lines().collect();
+    let after: Vec<&str> = after.lines().collect();
+
+    // Most edits only touch part of a note, so skip the lines both versions share
+    // at the start and end before comparing the rest
+    let prefix = before
+        .iter()
+        .zip(&after)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let before = &before[prefix..before.len() - suffix];
+    let after = &after[prefix..after.len() - suffix];
+
+    // Length of the longest common subsequence, one row at a time
+    let mut previous = vec![0; after.len() + 1];
+    for before_line in before {
+        let mut current = vec![0; after.len() + 1];
+        for (j, after_line) in after.iter().enumerate() {
+            current[j + 1] = if before_line == after_line {
+                previous[j] + 1
+            } else {
+                previous[j + 1].max(current[j])
+            };
+        }
+        previous = current;
+    }
+
+    let common = previous[after.len()];
+
+    (after.len() - common, before.len() - common)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("note", "note", None ; "no anchor")]
+    #[test_case("note#Some heading", "note", Some(Anchor::Heading("Some heading".into())) ; "heading")]
+    #[test_case("note#Parent#Child", "note", Some(Anchor::Heading("Child".into())) ; "nested heading")]
+    #[test_case("note#^abc123", "note", Some(Anchor::Block("abc123".into())) ; "block")]
+    #[test_case("folder/note.md:42", "folder/note.md", Some(Anchor::Line(42)) ; "line")]
+    #[test_case("note:abc", "note:abc", None ; "non-numeric suffix")]
+    fn split_anchor_parses_suffixes(input: &str, note: &str, anchor: Option<Anchor>) {
+        assert_eq!(split_anchor(input), (note, anchor));
+    }
+
+    #[test_case(Anchor::Heading("second  Heading".into()), Some(7) ; "heading")]
+    #[test_case(Anchor::Block("ref".into()), Some(3) ; "block")]
+    #[test_case(Anchor::Line(2), Some(2) ; "line")]
+    #[test_case(Anchor::Line(7), Some(7) ; "last line")]
+    #[test_case(Anchor::Line(8), None ; "line past the end")]
+    #[test_case(Anchor::Line(0), None ; "line zero")]
+    #[test_case(Anchor::Heading("code".into()), None ; "heading in code block")]
+    fn anchor_line_finds_position(anchor: Anchor, expected: Option<usize>) {
+        let contents = "# First\n\nA paragraph ^ref\n```\n# code\n```\n## Second heading\n";
+
+        assert_eq!(anchor_line(contents, &anchor), expected);
+    }
+
+    #[test_case("Text x^ref\n", None ; "caret within a word")]
+    #[test_case("```\nText ^ref\n```\n", None ; "in code block")]
+    #[test_case("^ref\n", Some(1) ; "on its own line")]
+    #[test_case("x^ref\n```\n^ref\n```\nText ^ref\n", Some(5) ; "after false matches")]
+    fn anchor_line_finds_block_ids(contents: &str, expected: Option<usize>) {
+        assert_eq!(
+            anchor_line(contents, &Anchor::Block("ref".into())),
+            expected
+        );
+    }
+
+    #[test_case("vim", vec!["vim"] ; "no arguments")]
+    #[test_case("code -w", vec!["code", "-w"] ; "with arguments")]
+    #[test_case(r#""/Applications/My Editor" --wait"#, vec!["/Applications/My Editor", "--wait"] ; "double quotes")]
+    #[test_case(r"emacs -nw 'a \b'", vec!["emacs", "-nw", r"a \b"] ; "single quotes")]
+    #[test_case(r"my\ editor", vec!["my editor"] ; "escaped space")]
+    fn split_command_handles_quotes(editor: &str, expected: Vec<&str>) {
+        assert_eq!(split_command(editor).unwrap(), expected);
+    }
+
+    #[test]
+    fn split_command_fails_on_unterminated_quotes() {
+        assert!(split_command(r#"code "--wait"#).is_err());
+    }
+
+    #[test_case("a\nb\nc", "a\nb\nc", (0, 0) ; "unchanged")]
+    #[test_case("a\nb\nc", "a\nb\nc\nd", (1, 0) ; "appended")]
+    #[test_case("a\nb\nc", "a\nc", (0, 1) ; "removed")]
+    #[test_case("a\nb\nc", "a\nB\nc", (1, 1) ; "changed")]
+    #[test_case("", "a\nb", (2, 0) ; "from empty")]
+    #[test_case("a\nb\nc\nd", "b\nx\nd\ne", (2, 2) ; "interleaved")]
+    fn line_changes_counts_added_and_removed(before: &str, after: &str, expected: (usize, usize)) {
+        assert_eq!(line_changes(before, after), expected);
+    }
+
+    #[test_case("vim", None, &["+3", "note.md"] ; "vim")]
+    #[test_case("/usr/bin/nano", None, &["+3", "note.md"] ; "nano with path")]
+    #[test_case("code", None, &["-g", "note.md:3"] ; "vscode")]
+    #[test_case("hx", None, &["note.md:3"] ; "helix")]
+    #[test_case("other", Some("--line={line} {file}"), &["--line=3", "note.md"] ; "pattern")]
+    #[test_case("other", None, &["note.md"] ; "unknown editor")]
+    fn jump_args_uses_editor_syntax(program: &str, pattern: Option<&str>, expected: &[&str]) {
+        assert_eq!(jump_args(program, "note.md", 3, pattern), expected);
+    }
+}
@@ -0,0 +1,134 @@
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Errors that scripts calling the CLI may want to tell apart, each with its own exit code.
+/// Anything else exits with 1, and invalid arguments exit with 2
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObxError {
+    /// Exits with 3
+    NoteNotFound(String),
+    /// Exits with 4
+    VaultNotFound(String),
+    /// A note, config file or input that couldn't be parsed. Exits with 5
+    Parse(String),
+    /// `$EDITOR` couldn't be found, couldn't be run or exited with an error. Exits with 6
+    EditorFailed(String),
+    /// The user declined a confirmation prompt. Exits with 7
+    Aborted,
+    /// A note name matched several notes and there was no prompt to choose one. Exits with 8
+    AmbiguousNote { query: String, matches: Vec<String> },
+}
+
+impl ObxError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ObxError::NoteNotFound(_) => 3,
+            ObxError::VaultNotFound(_) => 4,
+            ObxError::Parse(_) => 5,
+            ObxError::EditorFailed(_) => 6,
+            ObxError::Aborted => 7,
+            ObxError::AmbiguousNote { .. } => 8,
+        }
+    }
+
+    /// The name of the error in `--format json` error objects
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ObxError::NoteNotFound(_) => "note_not_found",
+            ObxError::VaultNotFound(_) => "vault_not_found",
+            ObxError::Parse(_) => "parse_error",
+            ObxError::EditorFailed(_) => "editor_failed",
+            ObxError::Aborted => "aborted",
+            ObxError::AmbiguousNote { .. } => "ambiguous_note",
+        }
+    }
+}
+
+impl fmt::Display for ObxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObxError::NoteNotFound(note) => write!(f, "Could not find note `{note}`"),
+            ObxError::VaultNotFound(vault) => write!(f, "Could not find vault `{vault}` in config"),
+            ObxError::Parse(message) | ObxError::EditorFailed(message) => write!(f, "{message}"),
+            ObxError::Aborted => write!(f, "Aborted"),
+            ObxError::AmbiguousNote { query, matches } => write!(
+                f,
+                "Note `{query}` is ambiguous, it could be any of:\n  {}",
+                matches.join("\n  ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ObxError {}
+
+/// The typed error behind an error, whether it was returned directly, added as context or
+/// is the source of another error
+fn find(error: &anyhow::Error) -> Option<&ObxError> {
+    error
+        .downcast_ref::<ObxError>()
+        .or_else(|| error.chain().find_map(|e| e.downcast_ref::<ObxError>()))
+}
+
+/// The code to exit with for an error, 1 unless it's one of the typed errors
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    find(error).map_or(1, ObxError::exit_code)
+}
+
+/// An error as printed with `--format json`, e.g.
+/// `{"error": {"kind": "note_not_found", "message": "...", "exit_code": 3}}`
+pub fn to_json(error: &anyhow::Error) -> Value {
+    json!({
+        "error": {
+            "kind": find(error).map_or("error", ObxError::kind),
+            "message": error.to_string(),
+            "exit_code": exit_code(error),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+    use test_case::test_case;
+
+    #[test_case(ObxError::NoteNotFound("note".into()).into(), 3 ; "typed error")]
+    #[test_case(anyhow!("Something went wrong"), 1 ; "other errors")]
+    #[test_case(
+        Err::<(), _>(ObxError::Aborted).context("Could not link mentions").unwrap_err(),
+        7 ;
+        "with context"
+    )]
+    #[test_case(
+        Err::<(), _>(anyhow!("no such file")).context(ObxError::Parse("Invalid note".into())).unwrap_err(),
+        5 ;
+        "as context"
+    )]
+    fn exit_codes(error: anyhow::Error, expected: i32) {
+        assert_eq!(exit_code(&error), expected);
+    }
+
+    #[test]
+    fn json_errors_include_the_kind() {
+        let error = ObxError::VaultNotFound("work".into()).into();
+
+        assert_eq!(
+            to_json(&error),
+            json!({
+                "error": {
+                    "kind": "vault_not_found",
+                    "message": "Could not find vault `work` in config",
+                    "exit_code": 4,
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn json_errors_default_to_a_general_error() {
+        let error = anyhow!("Something went wrong");
+
+        assert_eq!(to_json(&error)["error"]["kind"], "error");
+    }
+}
@@ -22,42 +22,94 @@ pub fn yaml_to_json_value(yaml: &YamlValue) -> JsonValue {
         YamlValue::Mapping(map) => {
             let mut json_map = serde_json::Map::new();
             for (k, v) in map {
-                let key = match k {
-                    YamlValue::String(s) => s.clone(),
-                    _ => k.as_str().unwrap_or_default().to_string(),
-                };
-                json_map.insert(key, yaml_to_json_value(v));
+                json_map.insert(yaml_to_string(k), yaml_to_json_value(v));
             }
             JsonValue::Object(json_map)
         }
-        YamlValue::Tagged(_) => todo!(),
+        // Tags like `!custom value` don't mean anything to Obsidian, so only the value is kept
+        YamlValue::Tagged(tagged) => yaml_to_json_value(&tagged.value),
+    }
+}
+
+/// A YAML value as a single line of text, e.g. for a table cell. Lists are comma separated
+/// and mappings are written as JSON
+pub fn yaml_to_string(yaml: &YamlValue) -> String {
+    match yaml {
+        YamlValue::Null => String::new(),
+        YamlValue::Bool(b) => b.to_string(),
+        YamlValue::Number(n) => n.to_string(),
+        YamlValue::String(s) => s.clone(),
+        YamlValue::Sequence(seq) => seq
+            .iter()
+            .map(yaml_to_string)
+            .collect::<Vec<String>>()
+            .join(", "),
+        YamlValue::Mapping(_) => yaml_to_json_value(yaml).to_string(),
+        YamlValue::Tagged(tagged) => yaml_to_string(&tagged.value),
     }
 }
 
 pub fn yaml_to_string_map(yaml_mapping: &serde_yaml::Mapping) -> HashMap<String, String> {
-    let mut mapping: HashMap<String, String> = HashMap::new();
-
-    for (k, v) in yaml_mapping {
-        let key: String = match k {
-            serde_yaml::Value::String(s) => s.clone(),
-            _ => panic!("YAML frontmatter key should be a string"),
-        };
-
-        let value: String = match v {
-            serde_yaml::Value::String(v) => v.clone(),
-            serde_yaml::Value::Number(v) => v.to_string(),
-            serde_yaml::Value::Bool(v) => v.to_string(),
-            serde_yaml::Value::Sequence(v) => v
-                .iter()
-                .map(|x| x.as_str().unwrap_or("default").to_string())
-                .collect::<Vec<String>>()
-                .join(", "),
-
-            _ => v.as_str().unwrap_or_default().to_string(),
-        };
-
-        mapping.insert(key, value);
+    yaml_mapping
+        .iter()
+        .map(|(k, v)| (yaml_to_string(k), yaml_to_string(v)))
+        .collect()
+}
+
+/// Split a markdown document into its `---` delimited YAML frontmatter and the
+/// remaining body. Returns `None` if the document doesn't start with a frontmatter block
+pub fn split_frontmatter(contents: &str) -> Option<(&str, &str)> {
+    let rest = contents
+        .strip_prefix("---\n")
+        .or_else(|| contents.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let frontmatter = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return Some((frontmatter, body));
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("---\nfoo: bar\n---\nbody\n", Some(("foo: bar\n", "body\n")) ; "frontmatter and body")]
+    #[test_case("---\nfoo: bar\n---", Some(("foo: bar\n", "")) ; "frontmatter without body")]
+    #[test_case("---\n---\nbody", Some(("", "body")) ; "empty frontmatter")]
+    #[test_case("---\r\nfoo: bar\r\n---\r\nbody", Some(("foo: bar\r\n", "body")) ; "crlf line endings")]
+    #[test_case("# Title\n---\nfoo: bar\n---\n", None ; "no leading frontmatter")]
+    #[test_case("---\nfoo: bar\n", None ; "unclosed frontmatter")]
+    fn split_frontmatter_separates_yaml_and_body(input: &str, expected: Option<(&str, &str)>) {
+        assert_eq!(split_frontmatter(input), expected);
     }
 
-    mapping
+    #[test_case("plain", "plain" ; "string")]
+    #[test_case("3", "3" ; "number")]
+    #[test_case("~", "" ; "null")]
+    #[test_case("[a, 2, true]", "a, 2, true" ; "mixed list")]
+    #[test_case("{a: 1}", r#"{"a":1}"# ; "mapping")]
+    #[test_case("!custom value", "value" ; "tagged")]
+    fn yaml_to_string_flattens_values(yaml: &str, expected: &str) {
+        let value: YamlValue = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(yaml_to_string(&value), expected);
+    }
+
+    #[test]
+    fn yaml_to_json_value_keeps_tagged_values_and_non_string_keys() {
+        let value: YamlValue = serde_yaml::from_str("2024: !date 2024-01-01").unwrap();
+
+        assert_eq!(
+            yaml_to_json_value(&value),
+            serde_json::json!({ "2024": "2024-01-01" })
+        );
+    }
 }
@@ -0,0 +1,510 @@
+use crate::vault::{normalize_name, VaultNote};
+use regex::Regex;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::Path,
+    sync::OnceLock,
+};
+
+/// A `[[wikilink]]` or `![[embed]]` found within a note
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    /// The note being linked to, without any `#heading`, `^block` or `|alias`
+    pub target: String,
+    pub kind: EdgeKind,
+    /// 1-based line number of the link within the note
+    pub line: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    Link,
+    Embed,
+}
+
+impl EdgeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeKind::Link => "link",
+            EdgeKind::Embed => "embed",
+        }
+    }
+}
+
+/// A resolved link between two notes, referencing them by their index in `VaultGraph::notes`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edge {
+    pub source: usize,
+    pub target: usize,
+    pub kind: EdgeKind,
+}
+
+fn wikilink_regex() -> &'static Regex {
+    static WIKILINK_RE: OnceLock<Regex> = OnceLock::new();
+    WIKILINK_RE.get_or_init(|| {
+        Regex::new(r"(!?)\[\[([^\[\]|#^]*)(?:[#^][^\[\]|]*)?(?:\|[^\[\]]*)?\]\]")
+            .expect("wikilink regex should compile")
+    })
+}
+
+fn inline_code_regex() -> &'static Regex {
+    static INLINE_CODE_RE: OnceLock<Regex> = OnceLock::new();
+    INLINE_CODE_RE.get_or_init(|| Regex::new(r"`[^`]*`").expect("inline code regex should compile"))
+}
+
+/// Find every wikilink and embed within a note, skipping code blocks and inline code
+pub fn note_links(note: &VaultNote) -> Vec<Link> {
+    let mut links = vec![];
+
+    for (line_number, line) in note.lines_outside_code() {
+        let line = inline_code_regex().replace_all(line, "");
+
+        for captures in wikilink_regex().captures_iter(&line) {
+            let target = captures[2].trim();
+
+            // Links such as [[#Heading]] point to the note they're in
+            if target.is_empty() {
+                continue;
+            }
+
+            let kind = match &captures[1] {
+                "!" => EdgeKind::Embed,
+                _ => EdgeKind::Link,
+            };
+
+            links.push(Link {
+                target: target.to_string(),
+                kind,
+                line: line_number,
+            });
+        }
+    }
+
+    links
+}
+
+/// The id Obsidian uses to link to a note, its vault-relative path without `.md`
+pub fn note_id(relative_path: &Path) -> String {
+    relative_path
+        .with_extension("")
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Every note in a vault, and the links between them
+#[derive(Debug)]
+pub struct VaultGraph {
+    pub notes: Vec<VaultNote>,
+    pub edges: Vec<Edge>,
+    /// Normalised basenames to the notes that have them, for resolving links
+    by_name: HashMap<String, Vec<usize>>,
+    /// The indexes in `edges` of the links from and to each note, so walking the graph
+    /// doesn't have to scan every edge
+    outgoing_edges: Vec<Vec<usize>>,
+    incoming_edges: Vec<Vec<usize>>,
+}
+
+impl VaultGraph {
+    pub fn build(notes: Vec<VaultNote>) -> Self {
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+        for (idx, note) in notes.iter().enumerate() {
+            by_name
+                .entry(normalize_name(&note.title()))
+                .or_default()
+                .push(idx);
+        }
+
+        let mut graph = Self {
+            outgoing_edges: vec![vec![]; notes.len()],
+            incoming_edges: vec![vec![]; notes.len()],
+            notes,
+            edges: vec![],
+            by_name,
+        };
+
+        let mut seen: HashSet<Edge> = HashSet::new();
+        for source in 0..graph.notes.len() {
+            for link in note_links(&graph.notes[source]) {
+                let Some(target) = graph.resolve(&link.target) else {
+                    continue;
+                };
+
+                let edge = Edge {
+                    source,
+                    target,
+                    kind: link.kind,
+                };
+
+                if seen.insert(edge) {
+                    graph.outgoing_edges[source].push(graph.edges.len());
+                    graph.incoming_edges[target].push(graph.edges.len());
+                    graph.edges.push(edge);
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Resolve a link target to a note in the same way Obsidian does: paths are matched
+    /// against the vault root, and bare names match any note with that basename. When
+    /// several notes share a basename, the one closest to the vault root wins
+    pub fn resolve(&self, target: &str) -> Option<usize> {
+        let target = normalize_name(target);
+        let name = target.rsplit('/').next().unwrap_or(&target);
+
+        let candidates = self.by_name.get(name)?;
+
+        candidates
+            .iter()
+            .copied()
+            .filter(|idx| {
+                !target.contains('/') || {
+                    let id = normalize_name(&self.id(*idx));
+                    id == target || id.ends_with(&format!("/{target}"))
+                }
+            })
+            .min_by_key(|idx| {
+                let path = &self.notes[*idx].relative_path;
+                (path.components().count(), path.clone())
+            })
+    }
+
+    pub fn id(&self, idx: usize) -> String {
+        note_id(&self.notes[idx].relative_path)
+    }
+
+    pub fn outgoing(&self, idx: usize) -> impl Iterator<Item = &Edge> {
+        self.outgoing_edges[idx]
+            .iter()
+            .map(move |e| &self.edges[*e])
+    }
+
+    pub fn incoming(&self, idx: usize) -> impl Iterator<Item = &Edge> {
+        self.incoming_edges[idx]
+            .iter()
+            .map(move |e| &self.edges[*e])
+    }
+
+    /// Notes within `hops` links of `seed`, following links in either direction
+    pub fn neighbourhood(&self, seed: usize, hops: usize) -> HashSet<usize> {
+        let mut visited = HashSet::from([seed]);
+        let mut queue = VecDeque::from([(seed, 0)]);
+
+        while let Some((idx, depth)) = queue.pop_front() {
+            if depth == hops {
+                continue;
+            }
+
+            let neighbours = self
+                .outgoing(idx)
+                .map(|e| e.target)
+                .chain(self.incoming(idx).map(|e| e.source))
+                .collect::<Vec<usize>>();
+
+            for neighbour in neighbours {
+                if visited.insert(neighbour) {
+                    queue.push_back((neighbour, depth + 1));
+                }
+            }
+        }
+
+        visited
+    }
+
+    /// The distinct notes `idx` links to, ignoring links from a note to itself
+    pub fn linked_notes(&self, idx: usize) -> Vec<usize> {
+        let mut targets: Vec<usize> = self
+            .outgoing(idx)
+            .map(|e| e.target)
+            .filter(|target| *target != idx)
+            .collect();
+        targets.sort();
+        targets.dedup();
+        targets
+    }
+
+    /// The distinct notes that link to `idx`, ignoring links from a note to itself
+    pub fn backlinked_notes(&self, idx: usize) -> Vec<usize> {
+        let mut sources: Vec<usize> = self
+            .incoming(idx)
+            .map(|e| e.source)
+            .filter(|source| *source != idx)
+            .collect();
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+
+    /// Notes that don't link to anything and aren't linked to from anywhere
+    pub fn orphans(&self) -> Vec<usize> {
+        (0..self.notes.len())
+            .filter(|idx| self.linked_notes(*idx).is_empty())
+            .filter(|idx| self.backlinked_notes(*idx).is_empty())
+            .collect()
+    }
+
+    /// Notes that are linked to, but don't link anywhere else
+    pub fn dead_ends(&self) -> Vec<usize> {
+        (0..self.notes.len())
+            .filter(|idx| self.linked_notes(*idx).is_empty())
+            .filter(|idx| !self.backlinked_notes(*idx).is_empty())
+            .collect()
+    }
+
+    /// The PageRank of every note, indexed the same as `notes`. Notes without any links
+    /// share their rank across the whole vault, so the ranks always sum to 1
+    pub fn pagerank(&self, damping: f64, iterations: usize) -> Vec<f64> {
+        let count = self.notes.len();
+        if count == 0 {
+            return vec![];
+        }
+
+        let linked: Vec<Vec<usize>> = (0..count).map(|idx| self.linked_notes(idx)).collect();
+        let mut ranks = vec![1.0 / count as f64; count];
+
+        for _ in 0..iterations {
+            let dangling: f64 = (0..count)
+                .filter(|idx| linked[*idx].is_empty())
+                .map(|idx| ranks[idx])
+                .sum();
+
+            let base = (1.0 - damping) / count as f64 + damping * dangling / count as f64;
+            let mut next = vec![base; count];
+
+            for (source, targets) in linked.iter().enumerate() {
+                for target in targets {
+                    next[*target] += damping * ranks[source] / targets.len() as f64;
+                }
+            }
+
+            ranks = next;
+        }
+
+        ranks
+    }
+
+    /// Groups of notes connected by links in either direction, largest first
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut seen: HashSet<usize> = HashSet::new();
+        let mut components = vec![];
+
+        for idx in 0..self.notes.len() {
+            if seen.contains(&idx) {
+                continue;
+            }
+
+            let mut component: Vec<usize> =
+                self.neighbourhood(idx, usize::MAX).into_iter().collect();
+            component.sort();
+            seen.extend(&component);
+            components.push(component);
+        }
+
+        // Stable, so components of the same size stay in vault order
+        components.sort_by_key(|c| std::cmp::Reverse(c.len()));
+        components
+    }
+
+    /// The shortest chain of links from `from` to `to`, including both ends. Links are
+    /// only followed forwards unless `undirected` is set
+    pub fn shortest_path(&self, from: usize, to: usize, undirected: bool) -> Option<Vec<usize>> {
+        let mut previous: HashMap<usize, usize> = HashMap::new();
+        let mut visited = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(idx) = queue.pop_front() {
+            if idx == to {
+                let mut path = vec![to];
+                while let Some(prev) = previous.get(path.last()?) {
+                    path.push(*prev);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let mut neighbours = self.linked_notes(idx);
+            if undirected {
+                neighbours.extend(self.backlinked_notes(idx));
+            }
+
+            for neighbour in neighbours {
+                if visited.insert(neighbour) {
+                    previous.insert(neighbour, idx);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use test_case::test_case;
+
+    fn note(path: &str, contents: &str) -> VaultNote {
+        VaultNote {
+            path: PathBuf::from("/vault").join(path),
+            relative_path: PathBuf::from(path),
+            contents: contents.to_string(),
+        }
+    }
+
+    #[test_case("[[note]]", "note", EdgeKind::Link ; "plain link")]
+    #[test_case("[[folder/note|alias]]", "folder/note", EdgeKind::Link ; "with alias")]
+    #[test_case("[[note#Heading|alias]]", "note", EdgeKind::Link ; "with heading")]
+    #[test_case("[[note#^block]]", "note", EdgeKind::Link ; "with block")]
+    #[test_case("![[image.png]]", "image.png", EdgeKind::Embed ; "embed")]
+    #[test_case("[[note |spaced alias]]", "note", EdgeKind::Link ; "trailing space")]
+    fn note_links_parses_wikilinks(text: &str, target: &str, kind: EdgeKind) {
+        let links = note_links(&note("source.md", text));
+
+        assert_eq!(
+            links,
+            vec![Link {
+                target: target.to_string(),
+                kind,
+                line: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn note_links_skips_code() {
+        let links = note_links(&note(
+            "source.md",
+            "`[[inline]]`\n```\n[[fenced]]\n```\n[[#Heading]]\n[[real]]",
+        ));
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].target, "real");
+        assert_eq!(links[0].line, 6);
+    }
+
+    #[test]
+    fn resolve_matches_basenames_and_paths() {
+        let graph = VaultGraph::build(vec![
+            note("a/deep/Note.md", ""),
+            note("b/note.md", ""),
+            note("other.md", ""),
+        ]);
+
+        assert_eq!(graph.resolve("note"), Some(1));
+        assert_eq!(graph.resolve("a/deep/note"), Some(0));
+        assert_eq!(graph.resolve("deep/Note.md"), Some(0));
+        assert_eq!(graph.resolve("Other"), Some(2));
+        assert_eq!(graph.resolve("missing"), None);
+    }
+
+    #[test]
+    fn build_dedupes_edges_and_skips_unresolved() {
+        let graph = VaultGraph::build(vec![
+            note("a.md", "[[b]] [[b|again]] ![[b]] [[missing]]"),
+            note("b.md", ""),
+        ]);
+
+        assert_eq!(
+            graph.edges,
+            vec![
+                Edge {
+                    source: 0,
+                    target: 1,
+                    kind: EdgeKind::Link
+                },
+                Edge {
+                    source: 0,
+                    target: 1,
+                    kind: EdgeKind::Embed
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn outgoing_and_incoming_index_edges_by_note() {
+        let graph = VaultGraph::build(vec![
+            note("a.md", "[[b]] [[c]]"),
+            note("b.md", "[[c]]"),
+            note("c.md", ""),
+        ]);
+
+        let targets = |idx| graph.outgoing(idx).map(|e| e.target).collect::<Vec<_>>();
+        let sources = |idx| graph.incoming(idx).map(|e| e.source).collect::<Vec<_>>();
+
+        assert_eq!(targets(0), vec![1, 2]);
+        assert_eq!(targets(2), Vec::<usize>::new());
+        assert_eq!(sources(2), vec![0, 1]);
+    }
+
+    #[test]
+    fn neighbourhood_follows_links_both_ways() {
+        let graph = VaultGraph::build(vec![
+            note("a.md", "[[b]]"),
+            note("b.md", "[[c]]"),
+            note("c.md", ""),
+            note("d.md", "[[a]]"),
+        ]);
+
+        assert_eq!(graph.neighbourhood(1, 1), HashSet::from([0, 1, 2]));
+        assert_eq!(graph.neighbourhood(1, 2), HashSet::from([0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn finds_orphans_and_dead_ends() {
+        let graph = VaultGraph::build(vec![
+            note("a.md", "[[b]] [[a]]"),
+            note("b.md", ""),
+            note("c.md", "[[c]]"),
+        ]);
+
+        assert_eq!(graph.orphans(), vec![2]);
+        assert_eq!(graph.dead_ends(), vec![1]);
+    }
+
+    #[test]
+    fn pagerank_favours_linked_notes() {
+        let graph = VaultGraph::build(vec![
+            note("a.md", "[[c]]"),
+            note("b.md", "[[c]]"),
+            note("c.md", "[[a]]"),
+        ]);
+
+        let ranks = graph.pagerank(0.85, 50);
+
+        assert!((ranks.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(ranks[2] > ranks[0] && ranks[0] > ranks[1]);
+    }
+
+    #[test]
+    fn components_are_largest_first() {
+        let graph = VaultGraph::build(vec![
+            note("a.md", ""),
+            note("b.md", "[[c]]"),
+            note("c.md", ""),
+            note("d.md", "[[c]]"),
+        ]);
+
+        assert_eq!(graph.components(), vec![vec![1, 2, 3], vec![0]]);
+    }
+
+    #[test]
+    fn shortest_path_follows_link_direction() {
+        let graph = VaultGraph::build(vec![
+            note("a.md", "[[b]] [[d]]"),
+            note("b.md", "[[c]]"),
+            note("c.md", ""),
+            note("d.md", "[[c]]"),
+        ]);
+
+        assert_eq!(graph.shortest_path(0, 2, false), Some(vec![0, 1, 2]));
+        assert_eq!(graph.shortest_path(2, 0, false), None);
+        assert_eq!(graph.shortest_path(2, 0, true), Some(vec![2, 1, 0]));
+        assert_eq!(graph.shortest_path(1, 1, false), Some(vec![1]));
+    }
+}
@@ -1,18 +1,41 @@
-use std::process::exit;
+use std::{path::PathBuf, process::exit};
 
-use clap::{Parser, Subcommand};
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
 
 pub mod app_settings;
 pub mod cli_config;
 pub mod commands;
+pub mod doctor;
+pub mod editor;
+pub mod error;
 pub mod formats;
+pub mod graph;
+pub mod mentions;
+pub mod output;
+pub mod stats;
+pub mod tasks;
+pub mod timestamps;
 pub mod util;
+pub mod vault;
 
 #[derive(Parser)]
 #[command(arg_required_else_help = true)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Use this config file instead of the default one
+    #[arg(long, global = true, env = "OBX_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// The vault to use instead of the current vault
+    #[arg(long, short = 'v', global = true)]
+    vault: Option<String>,
+
+    /// The format to print in, or the file format for `graph export`, the only command
+    /// that takes dot and graphml. Defaults to the vault's `output_format`, or pretty
+    #[arg(long, short = 'f', global = true)]
+    format: Option<output::Format>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -26,6 +49,12 @@ enum Commands {
     /// Commands for interacting with vaults
     Vaults(commands::vaults::VaultsCommand),
 
+    /// Commands for finding and managing tasks across a vault
+    Tasks(commands::tasks::TasksCommand),
+
+    /// Commands for exploring the links between notes
+    Graph(commands::graph::GraphCommand),
+
     /// Commands for managing config
     Config(commands::config::ConfigCommand),
 }
@@ -33,26 +62,67 @@ enum Commands {
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
+    if let Some(config) = &cli.config {
+        cli_config::set_config_file(config.clone());
+    }
+    if let Some(vault) = &cli.vault {
+        cli_config::set_vault_override(vault.clone());
+    }
+    if let Some(format) = cli.format {
+        let exports_graph = matches!(&cli.command, Some(Commands::Graph(args)) if args.is_export());
+        if format.is_graph_only() && !exports_graph {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--format dot and --format graphml can only be used with `graph export`",
+                )
+                .exit();
+        }
+
+        output::set_format_override(format);
+    }
+
     let res = match &cli.command {
         Some(Commands::Init(args)) => commands::init::entry(args),
         Some(Commands::Notes(args)) => commands::notes::entry(args),
         Some(Commands::Vaults(args)) => commands::vaults::entry(args),
+        Some(Commands::Tasks(args)) => commands::tasks::entry(args),
+        Some(Commands::Graph(args)) => commands::graph::entry(args),
         Some(Commands::Config(args)) => commands::config::entry(args),
-        None => {
-            todo!("Needs a sub-command");
-        }
+        None => Err(anyhow::anyhow!("A command is required, see `obx --help`")),
     };
 
-    match res {
-        Ok(Some(content)) => {
-            println!("{content}");
+    let res = res.and_then(|output| match output {
+        Some(output) => print_output(&output, &cli.command),
+        None => Ok(()),
+    });
+
+    if let Err(e) = res {
+        if let Some(failed) = e.downcast_ref::<output::FailedWithOutput>() {
+            let _ = print_output(&failed.output, &cli.command);
         }
-        Ok(None) => {}
-        Err(e) => {
-            eprintln!("{e}");
-            exit(1)
+
+        // Only `--format` is checked, as reading the vault's `output_format` could be what failed
+        match output::format_override() {
+            Some(output::Format::Json | output::Format::Ndjson) => {
+                eprintln!("{}", error::to_json(&e))
+            }
+            _ => eprintln!("{e}"),
         }
+        exit(error::exit_code(&e))
     }
 
     Ok(())
 }
+
+fn print_output(output: &output::Output, command: &Option<Commands>) -> anyhow::Result<()> {
+    // Config commands have to work without a valid vault, so don't look for its format
+    let uses_vault = !matches!(command, Some(Commands::Config(_) | Commands::Init(_)));
+
+    let vault = match uses_vault && output::format_override().is_none() {
+        true => util::get_current_vault().ok(),
+        false => None,
+    };
+
+    output::print(output, vault.as_ref())
+}
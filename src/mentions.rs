@@ -0,0 +1,191 @@
+use crate::vault::VaultNote;
+use regex::Regex;
+use serde::Serialize;
+use std::{ops::Range, path::PathBuf, sync::OnceLock};
+
+/// A plain-text reference to a note's title or alias that hasn't been linked yet
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Mention {
+    /// Path to the note containing the mention, relative to the vault root
+    pub file: PathBuf,
+    /// 1-based line number of the mention within the note
+    pub line: usize,
+    /// 1-based column, in characters, where the mention starts
+    pub column: usize,
+    /// The mention as it was written
+    pub text: String,
+    /// The full line the mention was found on
+    pub context: String,
+    /// Byte offsets of the mention within the line
+    #[serde(skip)]
+    pub range: Range<usize>,
+}
+
+/// Parts of a line that should never be treated as a mention: inline code, wikilinks,
+/// markdown links, bare URLs and `#tags`
+fn excluded_regex() -> &'static Regex {
+    static EXCLUDED_RE: OnceLock<Regex> = OnceLock::new();
+    EXCLUDED_RE.get_or_init(|| {
+        Regex::new(
+            r"`[^`]*`|!?\[\[[^\]]*\]\]|!?\[[^\]]*\]\([^)]*\)|<[a-z]+://[^>]*>|[a-z][a-z0-9+.-]*://\S+|#[\p{L}\p{N}_/-]+",
+        )
+        .expect("excluded regex should compile")
+    })
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Find whole-word, case-insensitive occurrences of any of the `terms` within a line.
+/// Where terms overlap, the longest match wins
+pub fn find_mentions(line: &str, terms: &[String]) -> Vec<Range<usize>> {
+    let excluded: Vec<Range<usize>> = excluded_regex()
+        .find_iter(line)
+        .map(|m| m.range())
+        .collect();
+
+    let mut candidates: Vec<Range<usize>> = vec![];
+
+    for term in terms.iter().filter(|t| !t.trim().is_empty()) {
+        let Ok(term_re) = Regex::new(&format!("(?i){}", regex::escape(term))) else {
+            continue;
+        };
+
+        for found in term_re.find_iter(line) {
+            let range = found.range();
+
+            let starts_word = line[..range.start]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !is_word_char(c));
+            let ends_word = line[range.end..]
+                .chars()
+                .next()
+                .is_none_or(|c| !is_word_char(c));
+            let is_excluded = excluded
+                .iter()
+                .any(|e| range.start < e.end && e.start < range.end);
+
+            if starts_word && ends_word && !is_excluded {
+                candidates.push(range);
+            }
+        }
+    }
+
+    candidates.sort_by_key(|r| (r.start, std::cmp::Reverse(r.end)));
+
+    let mut mentions: Vec<Range<usize>> = vec![];
+    for candidate in candidates {
+        if mentions
+            .last()
+            .is_none_or(|last| candidate.start >= last.end)
+        {
+            mentions.push(candidate);
+        }
+    }
+
+    mentions
+}
+
+/// Every unlinked mention of the `terms` within a note, skipping frontmatter and code blocks
+pub fn note_mentions(note: &VaultNote, terms: &[String]) -> Vec<Mention> {
+    note.lines_outside_code()
+        .into_iter()
+        .flat_map(|(line_number, line)| {
+            find_mentions(line, terms)
+                .into_iter()
+                .map(move |range| Mention {
+                    file: note.relative_path.clone(),
+                    line: line_number,
+                    column: line[..range.start].chars().count() + 1,
+                    text: line[range.clone()].to_string(),
+                    context: line.to_string(),
+                    range,
+                })
+        })
+        .collect()
+}
+
+/// Replace each of the `ranges` within a line with a wikilink to `target`, keeping the
+/// original text as the link's display text when it differs from the target
+pub fn link_mentions(line: &str, ranges: &[Range<usize>], target: &str) -> String {
+    let mut linked = String::with_capacity(line.len());
+    let mut last_end = 0;
+
+    for range in ranges {
+        let text = &line[range.clone()];
+
+        linked.push_str(&line[last_end..range.start]);
+        if text == target {
+            linked.push_str(&format!("[[{target}]]"));
+        } else {
+            linked.push_str(&format!("[[{target}|{text}]]"));
+        }
+        last_end = range.end;
+    }
+
+    linked.push_str(&line[last_end..]);
+    linked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn terms(terms: &[&str]) -> Vec<String> {
+        terms.iter().map(|t| t.to_string()).collect()
+    }
+
+    fn mentioned<'a>(line: &'a str, search: &[&str]) -> Vec<&'a str> {
+        find_mentions(line, &terms(search))
+            .into_iter()
+            .map(|r| &line[r])
+            .collect()
+    }
+
+    #[test_case("Met at the standup", vec!["standup"] ; "plain")]
+    #[test_case("The Standup.", vec!["Standup"] ; "case insensitive")]
+    #[test_case("standups and upstanding", vec![] ; "whole words only")]
+    #[test_case("[[standup]] and [[other|standup]]", vec![] ; "wikilinks")]
+    #[test_case("[the standup](standup.md)", vec![] ; "markdown links")]
+    #[test_case("`standup` code", vec![] ; "inline code")]
+    #[test_case("https://example.com/standup", vec![] ; "urls")]
+    #[test_case("#standup and #team/standup", vec![] ; "tags")]
+    fn find_mentions_skips_links_and_code(line: &str, expected: Vec<&str>) {
+        assert_eq!(mentioned(line, &["standup"]), expected);
+    }
+
+    #[test]
+    fn find_mentions_prefers_longest_term() {
+        assert_eq!(
+            mentioned("the daily sync notes", &["daily", "daily sync"]),
+            vec!["daily sync"]
+        );
+    }
+
+    #[test]
+    fn note_mentions_skips_frontmatter_and_code_blocks() {
+        let note = VaultNote {
+            path: PathBuf::from("/vault/note.md"),
+            relative_path: PathBuf::from("note.md"),
+            contents: "---\ntopic: standup\n---\n```\nstandup\n```\nthe standup\n".to_string(),
+        };
+
+        let mentions = note_mentions(&note, &terms(&["standup"]));
+
+        assert_eq!(mentions.len(), 1);
+        assert_eq!((mentions[0].line, mentions[0].column), (7, 5));
+    }
+
+    #[test]
+    fn link_mentions_keeps_original_text() {
+        let line = "the standup and Daily Sync";
+
+        assert_eq!(
+            link_mentions(line, &[4..11, 16..26], "standup"),
+            "the [[standup]] and [[standup|Daily Sync]]"
+        );
+    }
+}
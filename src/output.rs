@@ -0,0 +1,343 @@
+use crate::cli_config::Vault;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{fmt, sync::OnceLock};
+use tabled::{builder::Builder, settings::Style};
+
+/// The formats any command can print in, chosen with the global `--format` flag
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Tables and text, for reading in a terminal
+    Pretty,
+    Json,
+    /// A line of JSON for each item in a list
+    Ndjson,
+    Yaml,
+    Csv,
+    Tsv,
+    /// A markdown table
+    Markdown,
+    /// Graphviz DOT, a file format for `graph export` only
+    Dot,
+    /// GraphML, a file format for `graph export` only
+    Graphml,
+}
+
+impl Format {
+    /// Whether this is one of the file formats only `graph export` can write
+    pub fn is_graph_only(self) -> bool {
+        matches!(self, Format::Dot | Format::Graphml)
+    }
+}
+
+/// How structured data looks in the pretty format
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pretty {
+    Text(String),
+    Table {
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+}
+
+/// What a command prints, rendered in whichever format was asked for
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    /// Structured data, along with how to show it in the pretty format
+    Data { data: Value, pretty: Pretty },
+    /// A file in a format of its own, such as an exported graph, printed as-is
+    Document(String),
+}
+
+impl Output {
+    /// A message for the user such as "Created note", which is `{"message": ...}` in the
+    /// structured formats
+    pub fn message(message: impl Into<String>) -> Self {
+        let message = message.into();
+
+        Output::Data {
+            data: json!({ "message": message }),
+            pretty: Pretty::Text(message),
+        }
+    }
+
+    /// Data shown as text in the pretty format
+    pub fn text(data: impl Serialize, text: impl Into<String>) -> anyhow::Result<Self> {
+        Ok(Output::Data {
+            data: serde_json::to_value(data)?,
+            pretty: Pretty::Text(text.into()),
+        })
+    }
+
+    /// Data shown as a table in the pretty format
+    pub fn table(
+        data: impl Serialize,
+        header: &[&str],
+        rows: Vec<Vec<String>>,
+    ) -> anyhow::Result<Self> {
+        Ok(Output::Data {
+            data: serde_json::to_value(data)?,
+            pretty: Pretty::Table {
+                header: header.iter().map(|column| column.to_string()).collect(),
+                rows,
+            },
+        })
+    }
+
+    pub fn document(contents: impl Into<String>) -> Self {
+        Output::Document(contents.into())
+    }
+
+    pub fn render(&self, format: Format) -> anyhow::Result<String> {
+        let (data, pretty) = match self {
+            Output::Document(contents) => return Ok(contents.clone()),
+            Output::Data { data, pretty } => (data, pretty),
+        };
+
+        let rendered = match format {
+            // Only `graph export` runs with the graph formats, and it prints its messages as pretty
+            Format::Pretty | Format::Dot | Format::Graphml => match pretty {
+                Pretty::Text(text) => text.clone(),
+                Pretty::Table { header, rows } => table(header, rows),
+            },
+            Format::Json => serde_json::to_string(data)?,
+            Format::Ndjson => match data {
+                Value::Array(items) => items
+                    .iter()
+                    .map(serde_json::to_string)
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join("\n"),
+                other => serde_json::to_string(other)?,
+            },
+            Format::Yaml => serde_yaml::to_string(data)?.trim_end().to_string(),
+            Format::Csv => delimited(data, ",", csv_field),
+            Format::Tsv => delimited(data, "\t", tsv_field),
+            Format::Markdown => markdown(data),
+        };
+
+        Ok(rendered)
+    }
+}
+
+/// A failed command that still has something to print, e.g. a report of the problems it
+/// failed on. The output goes to stdout like any other, followed by the error
+#[derive(Debug)]
+pub struct FailedWithOutput {
+    pub output: Output,
+    pub message: String,
+}
+
+impl fmt::Display for FailedWithOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for FailedWithOutput {}
+
+/// Overrides from the global command line flags, set once at startup
+static FORMAT_OVERRIDE: OnceLock<Format> = OnceLock::new();
+
+/// Print in `format` instead of the vault's `output_format`, from `--format`
+pub fn set_format_override(format: Format) {
+    let _ = FORMAT_OVERRIDE.set(format);
+}
+
+pub fn format_override() -> Option<Format> {
+    FORMAT_OVERRIDE.get().copied()
+}
+
+/// The format to print in: the one given with `--format`, otherwise the vault's
+/// `output_format` setting, otherwise pretty
+pub fn format(vault: Option<&Vault>) -> Format {
+    format_override()
+        .or_else(|| {
+            vault
+                .and_then(|vault| vault.settings.output_format.as_deref())
+                .and_then(|format| Format::from_str(format, true).ok())
+        })
+        .unwrap_or(Format::Pretty)
+}
+
+pub fn print(output: &Output, vault: Option<&Vault>) -> anyhow::Result<()> {
+    println!("{}", output.render(format(vault))?);
+
+    Ok(())
+}
+
+/// A table in the style every command uses
+pub fn table<H: AsRef<str>>(header: &[H], rows: &[Vec<String>]) -> String {
+    let mut builder = Builder::new();
+
+    for row in rows {
+        builder.push_record(row.clone());
+    }
+    builder.insert_record(0, header.iter().map(|column| column.as_ref().to_string()));
+
+    let mut table = builder.build();
+    table.with(Style::sharp());
+
+    format!("{table}")
+}
+
+/// The columns and rows of data for the tabular formats. A list of objects has a row for
+/// each object and a column for every key, a single object is one row, and anything else
+/// is a single `value` column
+fn tabulate(data: &Value) -> (Vec<String>, Vec<Vec<String>>) {
+    let items: Vec<&Value> = match data {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+
+    if !items.iter().all(|item| item.is_object()) {
+        let rows = items.iter().map(|item| vec![cell(item)]).collect();
+        return (vec!["value".to_string()], rows);
+    }
+
+    let mut columns: Vec<String> = vec![];
+    for key in items
+        .iter()
+        .filter_map(|item| item.as_object())
+        .flat_map(|o| o.keys())
+    {
+        if !columns.contains(key) {
+            columns.push(key.clone());
+        }
+    }
+
+    let rows = items
+        .iter()
+        .map(|item| {
+            columns
+                .iter()
+                .map(|column| item.get(column).map(cell).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+
+    (columns, rows)
+}
+
+/// A value flattened into a single cell. Lists of plain values are comma separated and
+/// anything more nested is left as JSON
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) if items.iter().all(|i| !i.is_array() && !i.is_object()) => {
+            items.iter().map(cell).collect::<Vec<String>>().join(", ")
+        }
+        other => other.to_string(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn tsv_field(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn delimited(data: &Value, separator: &str, escape: fn(&str) -> String) -> String {
+    let (columns, rows) = tabulate(data);
+
+    let line = |fields: &[String]| {
+        fields
+            .iter()
+            .map(|field| escape(field))
+            .collect::<Vec<String>>()
+            .join(separator)
+    };
+
+    let mut lines = vec![line(&columns)];
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines.join("\n")
+}
+
+fn markdown(data: &Value) -> String {
+    let (columns, rows) = tabulate(data);
+
+    let line = |fields: &[String]| {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|field| field.replace('|', "\\|").replace('\n', "<br>"))
+            .collect();
+        format!("| {} |", fields.join(" | "))
+    };
+
+    let divider = vec!["---".to_string(); columns.len()];
+    let mut lines = vec![line(&columns), line(&divider)];
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn notes() -> Output {
+        Output::table(
+            json!([
+                {"note": "a", "tags": ["x", "y"]},
+                {"note": "b, c", "links": 2}
+            ]),
+            &["Note"],
+            vec![vec!["a".to_string()], vec!["b, c".to_string()]],
+        )
+        .unwrap()
+    }
+
+    #[test_case(Format::Json, r#"[{"note":"a","tags":["x","y"]},{"note":"b, c","links":2}]"# ; "json")]
+    #[test_case(Format::Ndjson, "{\"note\":\"a\",\"tags\":[\"x\",\"y\"]}\n{\"note\":\"b, c\",\"links\":2}" ; "ndjson")]
+    #[test_case(Format::Csv, "note,tags,links\na,\"x, y\",\n\"b, c\",,2" ; "csv")]
+    #[test_case(Format::Tsv, "note\ttags\tlinks\na\tx, y\t\nb, c\t\t2" ; "tsv")]
+    #[test_case(Format::Markdown, "| note | tags | links |\n| --- | --- | --- |\n| a | x, y |  |\n| b, c |  | 2 |" ; "markdown")]
+    fn renders_lists_of_objects(format: Format, expected: &str) {
+        assert_eq!(notes().render(format).unwrap(), expected);
+    }
+
+    #[test]
+    fn renders_tables_when_pretty() {
+        let rendered = notes().render(Format::Pretty).unwrap();
+
+        assert!(rendered.starts_with("┌"));
+        assert!(rendered.contains("│ b, c │"));
+    }
+
+    #[test_case(Format::Pretty, "Created note" ; "pretty")]
+    #[test_case(Format::Json, r#"{"message":"Created note"}"# ; "json")]
+    #[test_case(Format::Yaml, "message: Created note" ; "yaml")]
+    #[test_case(Format::Csv, "message\nCreated note" ; "csv")]
+    fn renders_messages(format: Format, expected: &str) {
+        assert_eq!(
+            Output::message("Created note").render(format).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn documents_ignore_the_format() {
+        let document = Output::document("digraph vault {}");
+
+        assert_eq!(document.render(Format::Json).unwrap(), "digraph vault {}");
+    }
+
+    #[test_case(json!(["a", "b"]), "value\na\nb" ; "list of strings")]
+    #[test_case(json!([["a", "b"], ["c"]]), "value\n\"a, b\"\nc" ; "nested lists")]
+    #[test_case(json!({"notes": 2, "links": null}), "notes,links\n2," ; "object")]
+    #[test_case(json!("line \"one\"\nline two"), "value\n\"line \"\"one\"\"\nline two\"" ; "escaping")]
+    fn csv_flattens_values(data: Value, expected: &str) {
+        assert_eq!(delimited(&data, ",", csv_field), expected);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{
+    cli_config::Vault,
+    graph::VaultGraph,
+    vault::{is_note, list_files, read_note_or_warn, VaultNote},
+};
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+    time::SystemTime,
+};
+
+/// How many of the most used tags and properties are reported
+const TOP_COUNT: usize = 10;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct NameCount {
+    pub name: String,
+    pub count: usize,
+}
+
+/// Notes created and modified within an ISO week, e.g. `2024-W07`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WeekActivity {
+    pub week: String,
+    pub created: usize,
+    pub modified: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct VaultStats {
+    pub vault: String,
+    pub notes: usize,
+    /// Files that aren't notes, by lowercase extension
+    pub attachments: BTreeMap<String, usize>,
+    pub words: usize,
+    /// Notes in each top-level folder, with `/` for notes in the root of the vault
+    pub folders: BTreeMap<String, usize>,
+    pub top_tags: Vec<NameCount>,
+    pub top_properties: Vec<NameCount>,
+    pub links: usize,
+    /// The average number of links per note
+    pub link_density: f64,
+    pub orphans: usize,
+    pub activity: Vec<WeekActivity>,
+}
+
+impl VaultStats {
+    /// Collect stats for a vault, with activity for the `weeks` weeks up to `today`
+    pub fn collect(vault: &Vault, weeks: usize, today: NaiveDate) -> anyhow::Result<Self> {
+        let files = list_files(vault)?;
+
+        let mut notes = vec![];
+        let mut attachments = BTreeMap::new();
+        let mut file_dates = vec![];
+
+        for path in &files {
+            if is_note(path) {
+                let Some(note) = read_note_or_warn(&vault.path, path) else {
+                    continue;
+                };
+                notes.push(note);
+                file_dates.push(file_dates_of(path));
+            } else {
+                let extension = path
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_lowercase())
+                    .unwrap_or_else(|| "none".to_string());
+                *attachments.entry(extension).or_insert(0) += 1;
+            }
+        }
+
+        let mut folders = BTreeMap::new();
+        let mut tags = vec![];
+        let mut properties = vec![];
+
+        for note in &notes {
+            *folders
+                .entry(top_level_folder(&note.relative_path))
+                .or_insert(0) += 1;
+            tags.extend(note.tags());
+
+            if let Some(serde_yaml::Value::Mapping(mapping)) = note.properties() {
+                properties.extend(
+                    mapping
+                        .keys()
+                        .filter_map(|k| k.as_str().map(str::to_string)),
+                );
+            }
+        }
+
+        let words = notes.iter().map(VaultNote::word_count).sum();
+        let graph = VaultGraph::build(notes);
+        let links = graph.edges.len();
+        let link_density = match graph.notes.len() {
+            0 => 0.0,
+            n => (links as f64 / n as f64 * 100.0).round() / 100.0,
+        };
+
+        Ok(Self {
+            vault: vault.name.clone(),
+            notes: graph.notes.len(),
+            attachments,
+            words,
+            folders,
+            top_tags: top_counts(tags),
+            top_properties: top_counts(properties),
+            links,
+            link_density,
+            orphans: graph.orphans().len(),
+            activity: weekly_activity(&file_dates, weeks, today),
+        })
+    }
+}
+
+/// The first folder of a path within the vault, or `/` for files in the root
+fn top_level_folder(relative_path: &Path) -> String {
+    let mut components = relative_path.components();
+    let first = components.next();
+
+    match (first, components.next()) {
+        (Some(folder), Some(_)) => folder.as_os_str().to_string_lossy().to_string(),
+        _ => "/".to_string(),
+    }
+}
+
+/// The most common names, most used first and then alphabetically
+fn top_counts(names: Vec<String>) -> Vec<NameCount> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for name in names {
+        *counts.entry(name).or_insert(0) += 1;
+    }
+
+    let mut counts: Vec<NameCount> = counts
+        .into_iter()
+        .map(|(name, count)| NameCount { name, count })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    counts.truncate(TOP_COUNT);
+
+    counts
+}
+
+/// When a file was created and last modified, where the file system records it
+fn file_dates_of(path: &Path) -> (Option<NaiveDate>, Option<NaiveDate>) {
+    let date = |time: io::Result<SystemTime>| {
+        time.ok()
+            .map(|time| DateTime::<Local>::from(time).date_naive())
+    };
+
+    match fs::metadata(path) {
+        Ok(metadata) => (date(metadata.created()), date(metadata.modified())),
+        Err(_) => (None, None),
+    }
+}
+
+fn iso_week(date: NaiveDate) -> String {
+    let week = date.iso_week();
+    format!("{:04}-W{:02}", week.year(), week.week())
+}
+
+/// Count the notes created and modified in each of the `weeks` weeks up to `today`,
+/// oldest first. Weeks without any activity are included so growth is easy to follow
+fn weekly_activity(
+    file_dates: &[(Option<NaiveDate>, Option<NaiveDate>)],
+    weeks: usize,
+    today: NaiveDate,
+) -> Vec<WeekActivity> {
+    let mut activity: Vec<WeekActivity> = (0..weeks)
+        .rev()
+        .filter_map(|weeks_ago| today.checked_sub_days(Days::new(7 * weeks_ago as u64)))
+        .map(|date| WeekActivity {
+            week: iso_week(date),
+            created: 0,
+            modified: 0,
+        })
+        .collect();
+
+    for (created, modified) in file_dates {
+        if let Some(week) = find_week(&mut activity, *created) {
+            week.created += 1;
+        }
+        if let Some(week) = find_week(&mut activity, *modified) {
+            week.modified += 1;
+        }
+    }
+
+    activity
+}
+
+fn find_week(activity: &mut [WeekActivity], date: Option<NaiveDate>) -> Option<&mut WeekActivity> {
+    let week = iso_week(date?);
+    activity.iter_mut().find(|activity| activity.week == week)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test_case("note.md", "/" ; "root")]
+    #[test_case("folder/note.md", "folder" ; "folder")]
+    #[test_case("folder/nested/note.md", "folder" ; "nested folder")]
+    fn top_level_folder_of_paths(path: &str, expected: &str) {
+        assert_eq!(top_level_folder(Path::new(path)), expected);
+    }
+
+    #[test]
+    fn top_counts_sorts_by_count_then_name() {
+        let names = ["b", "a", "c", "a", "b", "a"].map(str::to_string).to_vec();
+
+        assert_eq!(
+            top_counts(names),
+            vec![
+                NameCount {
+                    name: "a".to_string(),
+                    count: 3
+                },
+                NameCount {
+                    name: "b".to_string(),
+                    count: 2
+                },
+                NameCount {
+                    name: "c".to_string(),
+                    count: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn weekly_activity_buckets_by_iso_week() {
+        let file_dates = [
+            (Some(date("2024-02-12")), Some(date("2024-02-19"))),
+            (Some(date("2024-02-14")), None),
+            // Too old to be included
+            (Some(date("2023-01-01")), Some(date("2023-01-01"))),
+        ];
+
+        assert_eq!(
+            weekly_activity(&file_dates, 3, date("2024-02-20")),
+            vec![
+                WeekActivity {
+                    week: "2024-W06".to_string(),
+                    created: 0,
+                    modified: 0
+                },
+                WeekActivity {
+                    week: "2024-W07".to_string(),
+                    created: 2,
+                    modified: 0
+                },
+                WeekActivity {
+                    week: "2024-W08".to_string(),
+                    created: 0,
+                    modified: 1
+                },
+            ]
+        );
+    }
+}
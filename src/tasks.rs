@@ -0,0 +1,588 @@
+use crate::vault::{inline_tags, VaultNote};
+use anyhow::{bail, Context};
+use chrono::{Days, Local, Months, NaiveDate, TimeDelta};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// A markdown checkbox (`- [ ] ...`) found within a note
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Task {
+    /// `<note>:<line>`, which can be passed back to other `tasks` commands
+    pub id: String,
+    /// Path to the note relative to the vault root
+    pub file: PathBuf,
+    /// 1-based line number of the task within the note
+    pub line: usize,
+    pub status: &'static str,
+    pub symbol: char,
+    pub description: String,
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub done: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+}
+
+impl Task {
+    pub fn is_done(&self) -> bool {
+        matches!(self.symbol, 'x' | 'X')
+    }
+
+    /// The description without any dates, recurrence rules or Dataview fields
+    pub fn summary(&self) -> String {
+        let summary = emoji_date_regex().replace_all(&self.description, "");
+        let summary = recurrence_regex().replace_all(&summary, "");
+        let summary = dataview_field_regex().replace_all(&summary, "");
+
+        summary.split_whitespace().collect::<Vec<&str>>().join(" ")
+    }
+}
+
+/// Human-readable names for the status symbols used by Obsidian and the Tasks plugin
+pub fn status_name(symbol: char) -> &'static str {
+    match symbol {
+        ' ' => "todo",
+        'x' | 'X' => "done",
+        '/' => "in-progress",
+        '-' => "cancelled",
+        _ => "custom",
+    }
+}
+
+/// Whether a task's status symbol matches a `--status` filter, which can either be
+/// a status name, `open` for anything not done or cancelled, or a literal symbol
+pub fn status_matches(filter: &str, symbol: char) -> bool {
+    match filter {
+        "open" => !matches!(symbol, 'x' | 'X' | '-'),
+        "todo" | "done" | "in-progress" | "cancelled" => status_name(symbol) == filter,
+        other => {
+            let mut chars = other.chars();
+            chars.next() == Some(symbol) && chars.next().is_none()
+        }
+    }
+}
+
+/// Parse a date argument, either `YYYY-MM-DD` or one of `today`, `tomorrow` or `yesterday`
+pub fn parse_date(arg: &str) -> Result<NaiveDate, String> {
+    let today = Local::now().date_naive();
+
+    let date = match arg {
+        "today" => Some(today),
+        "tomorrow" => today.checked_add_days(Days::new(1)),
+        "yesterday" => today.checked_sub_days(Days::new(1)),
+        _ => NaiveDate::parse_from_str(arg, "%Y-%m-%d").ok(),
+    };
+
+    date.ok_or_else(|| {
+        format!("invalid date `{arg}`, expected YYYY-MM-DD, today, tomorrow or yesterday")
+    })
+}
+
+fn task_regex() -> &'static Regex {
+    static TASK_RE: OnceLock<Regex> = OnceLock::new();
+    TASK_RE.get_or_init(|| {
+        Regex::new(r"^\s*(?:>\s*)*(?:[-*+]|\d+[.)])\s+\[(.)\]\s?(.*)$")
+            .expect("task regex should compile")
+    })
+}
+
+fn emoji_date_regex() -> &'static Regex {
+    static DATE_RE: OnceLock<Regex> = OnceLock::new();
+    DATE_RE.get_or_init(|| {
+        Regex::new(r"(📅|⏳|🛫|✅)\x{FE0F}?\s*(\d{4}-\d{2}-\d{2})")
+            .expect("emoji date regex should compile")
+    })
+}
+
+fn dataview_field_regex() -> &'static Regex {
+    static FIELD_RE: OnceLock<Regex> = OnceLock::new();
+    FIELD_RE.get_or_init(|| {
+        Regex::new(r"[\[(](due|scheduled|start|completion|repeat)::\s*([^\])]+)[\])]")
+            .expect("dataview field regex should compile")
+    })
+}
+
+fn recurrence_regex() -> &'static Regex {
+    static RECURRENCE_RE: OnceLock<Regex> = OnceLock::new();
+    RECURRENCE_RE.get_or_init(|| {
+        Regex::new(r"🔁\x{FE0F}?\s*([^📅⏳🛫✅➕⏫🔼🔽🔺⏬#\[(^]+)")
+            .expect("recurrence regex should compile")
+    })
+}
+
+/// Parse a single line of markdown into a task, returning `None` if it isn't a checkbox
+pub fn parse_task(file: &Path, line_number: usize, line: &str) -> Option<Task> {
+    let captures = task_regex().captures(line)?;
+    let symbol = captures[1].chars().next()?;
+    let description = captures[2].trim_end().to_string();
+
+    let mut task = Task {
+        id: format!("{}:{line_number}", file.display()),
+        file: file.to_path_buf(),
+        line: line_number,
+        status: status_name(symbol),
+        symbol,
+        tags: inline_tags(&description),
+        description,
+        due: None,
+        scheduled: None,
+        start: None,
+        done: None,
+        recurrence: None,
+    };
+
+    for captures in emoji_date_regex().captures_iter(&task.description) {
+        let Ok(date) = NaiveDate::parse_from_str(&captures[2], "%Y-%m-%d") else {
+            continue;
+        };
+
+        match &captures[1] {
+            "📅" => task.due = Some(date),
+            "⏳" => task.scheduled = Some(date),
+            "🛫" => task.start = Some(date),
+            "✅" => task.done = Some(date),
+            _ => {}
+        }
+    }
+
+    for captures in dataview_field_regex().captures_iter(&task.description) {
+        let value = captures[2].trim();
+        let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+
+        match &captures[1] {
+            "due" => task.due = task.due.or(date),
+            "scheduled" => task.scheduled = task.scheduled.or(date),
+            "start" => task.start = task.start.or(date),
+            "completion" => task.done = task.done.or(date),
+            "repeat" => task.recurrence = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    if let Some(captures) = recurrence_regex().captures(&task.description) {
+        task.recurrence = Some(captures[1].trim().to_string());
+    }
+
+    Some(task)
+}
+
+fn done_date_regex() -> &'static Regex {
+    static DONE_RE: OnceLock<Regex> = OnceLock::new();
+    DONE_RE.get_or_init(|| {
+        Regex::new(r"\s*(?:✅\x{FE0F}?\s*\d{4}-\d{2}-\d{2}|\[completion::[^\]]*\])")
+            .expect("done date regex should compile")
+    })
+}
+
+/// How often a recurring task repeats, parsed from rules like `every 2 weeks`
+#[derive(Debug, PartialEq)]
+struct Recurrence {
+    interval: u32,
+    unit: RecurrenceUnit,
+    /// `when done` rules recur relative to the completion date rather than the task's dates
+    when_done: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum RecurrenceUnit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Recurrence {
+    fn parse(rule: &str) -> Option<Self> {
+        let rule = rule.trim().to_lowercase();
+        let when_done = rule.ends_with("when done");
+        let rule = rule.trim_end_matches("when done").trim();
+
+        let mut words = rule.strip_prefix("every")?.split_whitespace();
+        let first = words.next()?;
+
+        let (interval, unit_word) = match first.parse::<u32>() {
+            Ok(n) => (n, words.next()?),
+            Err(_) if first == "other" => (2, words.next()?),
+            Err(_) => (1, first),
+        };
+
+        // Rules such as `every week on Monday` aren't supported
+        if words.next().is_some() || interval == 0 {
+            return None;
+        }
+
+        let unit = match unit_word.trim_end_matches('s') {
+            "day" => RecurrenceUnit::Day,
+            "week" => RecurrenceUnit::Week,
+            "month" => RecurrenceUnit::Month,
+            "year" => RecurrenceUnit::Year,
+            _ => return None,
+        };
+
+        Some(Self {
+            interval,
+            unit,
+            when_done,
+        })
+    }
+
+    fn next(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self.unit {
+            RecurrenceUnit::Day => date.checked_add_days(Days::new(self.interval.into())),
+            RecurrenceUnit::Week => date.checked_add_days(Days::new(u64::from(self.interval) * 7)),
+            RecurrenceUnit::Month => date.checked_add_months(Months::new(self.interval)),
+            RecurrenceUnit::Year => date.checked_add_months(Months::new(self.interval * 12)),
+        }
+    }
+}
+
+fn with_status(line: &str, symbol: char) -> anyhow::Result<String> {
+    let captures = task_regex().captures(line).context("line is not a task")?;
+    let status = captures.get(1).context("line is not a task")?;
+
+    Ok(format!(
+        "{}{symbol}{}",
+        &line[..status.start()],
+        &line[status.end()..]
+    ))
+}
+
+fn block_id_regex() -> &'static Regex {
+    static BLOCK_ID_RE: OnceLock<Regex> = OnceLock::new();
+    BLOCK_ID_RE.get_or_init(|| {
+        Regex::new(r"\s+\^[A-Za-z0-9-]+\s*$").expect("block id regex should compile")
+    })
+}
+
+/// Split a line into its text and the block ID it ends with, e.g. ` ^abc`, which has to
+/// stay last for `[[note#^abc]]` links to keep working
+fn split_block_id(line: &str) -> (&str, &str) {
+    match block_id_regex().find(line) {
+        Some(block_id) => (&line[..block_id.start()], block_id.as_str()),
+        None => (line, ""),
+    }
+}
+
+fn remove_done_date(line: &str) -> String {
+    done_date_regex().replace_all(line, "").to_string()
+}
+
+/// Move every emoji and Dataview date within the line by `offset`
+fn shift_dates(line: &str, offset: TimeDelta) -> String {
+    let line = emoji_date_regex().replace_all(line, |c: &Captures| shift_captured_date(c, offset));
+
+    dataview_field_regex()
+        .replace_all(&line, |c: &Captures| shift_captured_date(c, offset))
+        .to_string()
+}
+
+fn shift_captured_date(captures: &Captures, offset: TimeDelta) -> String {
+    let whole = captures
+        .get(0)
+        .expect("captures should contain the whole match");
+    let value = captures
+        .get(2)
+        .expect("date regexes should capture the value");
+
+    let shifted = NaiveDate::parse_from_str(value.as_str().trim(), "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.checked_add_signed(offset))
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| value.as_str().to_string());
+
+    format!(
+        "{}{shifted}{}",
+        &whole.as_str()[..value.start() - whole.start()],
+        &whole.as_str()[value.end() - whole.start()..]
+    )
+}
+
+/// Create the next occurrence of a recurring task, in the same way as the Tasks plugin:
+/// the reference date (due, then scheduled, then start) moves forward by the recurrence
+/// rule and every other date moves by the same amount
+fn next_occurrence(
+    line: &str,
+    task: &Task,
+    rule: &str,
+    today: NaiveDate,
+) -> anyhow::Result<String> {
+    let recurrence =
+        Recurrence::parse(rule).with_context(|| format!("unsupported recurrence rule `{rule}`"))?;
+
+    let reference = task
+        .due
+        .or(task.scheduled)
+        .or(task.start)
+        .context("recurring tasks need a due, scheduled or start date")?;
+
+    let base = if recurrence.when_done {
+        today
+    } else {
+        reference
+    };
+    let next_reference = recurrence
+        .next(base)
+        .context("next occurrence is out of range")?;
+
+    let offset = next_reference.signed_duration_since(reference);
+
+    with_status(&shift_dates(&remove_done_date(line), offset), ' ')
+}
+
+/// Toggle a task line between done and not done. Completing a task appends a `✅` date
+/// before any block ID, and completing a recurring task also inserts the next occurrence
+/// above it. Like the Tasks plugin, the next occurrence doesn't get the block ID, as it
+/// would no longer be unique
+///
+/// Returns the lines that should replace the task, and whether the task is now done
+pub fn toggle_task(line: &str, today: NaiveDate) -> anyhow::Result<(Vec<String>, bool)> {
+    let Some(task) = parse_task(Path::new(""), 0, line) else {
+        bail!("line is not a task");
+    };
+
+    if task.is_done() {
+        let undone = with_status(&remove_done_date(line), ' ')?;
+        return Ok((vec![undone], false));
+    }
+
+    let (text, block_id) = split_block_id(line.trim_end());
+    let completed = format!(
+        "{} ✅ {}{block_id}",
+        with_status(text, 'x')?,
+        today.format("%Y-%m-%d")
+    );
+
+    match &task.recurrence {
+        Some(rule) => {
+            let next = next_occurrence(text, &task, rule, today)?;
+            Ok((vec![next, completed], true))
+        }
+        None => Ok((vec![completed], true)),
+    }
+}
+
+/// Format a new task in the order the Tasks plugin writes its fields
+pub fn format_new_task(
+    description: &str,
+    scheduled: Option<NaiveDate>,
+    due: Option<NaiveDate>,
+) -> String {
+    let mut task = format!("- [ ] {}", description.trim());
+
+    if let Some(scheduled) = scheduled {
+        task.push_str(&format!(" ⏳ {}", scheduled.format("%Y-%m-%d")));
+    }
+
+    if let Some(due) = due {
+        task.push_str(&format!(" 📅 {}", due.format("%Y-%m-%d")));
+    }
+
+    task
+}
+
+/// Find every task within a note, skipping anything inside fenced code blocks
+pub fn note_tasks(note: &VaultNote) -> Vec<Task> {
+    note.lines_outside_code()
+        .into_iter()
+        .filter_map(|(line_number, line)| parse_task(&note.relative_path, line_number, line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn parse(line: &str) -> Option<Task> {
+        parse_task(Path::new("note.md"), 1, line)
+    }
+
+    fn date(s: &str) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+    }
+
+    #[test_case("- [ ] todo", ' ' ; "unchecked")]
+    #[test_case("- [x] done", 'x' ; "checked")]
+    #[test_case("* [/] in progress", '/' ; "asterisk bullet")]
+    #[test_case("1. [-] cancelled", '-' ; "numbered list")]
+    #[test_case("    - [?] question", '?' ; "indented custom status")]
+    #[test_case("> - [ ] quoted", ' ' ; "within a callout")]
+    fn parses_task_status(line: &str, symbol: char) {
+        assert_eq!(parse(line).unwrap().symbol, symbol);
+    }
+
+    #[test_case("- not a task" ; "plain bullet")]
+    #[test_case("[ ] no bullet" ; "no bullet")]
+    #[test_case("-[ ] no space" ; "no space after bullet")]
+    fn ignores_non_tasks(line: &str) {
+        assert_eq!(parse(line), None);
+    }
+
+    #[test]
+    fn parses_emoji_dates() {
+        let task =
+            parse("- [x] Ship it 🛫 2024-01-01 ⏳ 2024-01-02 📅 2024-01-03 ✅ 2024-01-04").unwrap();
+
+        assert_eq!(task.start, date("2024-01-01"));
+        assert_eq!(task.scheduled, date("2024-01-02"));
+        assert_eq!(task.due, date("2024-01-03"));
+        assert_eq!(task.done, date("2024-01-04"));
+    }
+
+    #[test]
+    fn parses_dataview_fields() {
+        let task = parse("- [ ] Ship it [due:: 2024-01-03] (scheduled:: 2024-01-02)").unwrap();
+
+        assert_eq!(task.due, date("2024-01-03"));
+        assert_eq!(task.scheduled, date("2024-01-02"));
+    }
+
+    #[test]
+    fn summary_strips_task_metadata() {
+        let task =
+            parse("- [ ] Water plants #home 🔁 every week 📅 2024-01-03 [scheduled:: 2024-01-01]")
+                .unwrap();
+
+        assert_eq!(task.summary(), "Water plants #home");
+    }
+
+    #[test]
+    fn parses_recurrence_and_tags() {
+        let task = parse("- [ ] Water plants #home 🔁 every week 📅 2024-01-03").unwrap();
+
+        assert_eq!(task.recurrence, Some("every week".to_string()));
+        assert_eq!(task.tags, vec!["home"]);
+    }
+
+    #[test_case("every day", Some((1, RecurrenceUnit::Day, false)) ; "every day")]
+    #[test_case("every 2 weeks", Some((2, RecurrenceUnit::Week, false)) ; "with interval")]
+    #[test_case("every other month", Some((2, RecurrenceUnit::Month, false)) ; "every other")]
+    #[test_case("Every year when done", Some((1, RecurrenceUnit::Year, true)) ; "when done")]
+    #[test_case("every week on Monday", None ; "unsupported weekday rule")]
+    #[test_case("every 0 days", None ; "zero interval")]
+    fn parses_recurrence_rules(rule: &str, expected: Option<(u32, RecurrenceUnit, bool)>) {
+        let expected = expected.map(|(interval, unit, when_done)| Recurrence {
+            interval,
+            unit,
+            when_done,
+        });
+
+        assert_eq!(Recurrence::parse(rule), expected);
+    }
+
+    #[test]
+    fn toggle_completes_task() {
+        let today = date("2024-01-20").unwrap();
+
+        assert_eq!(
+            toggle_task("- [ ] Ship it 📅 2024-01-10", today).unwrap(),
+            (
+                vec!["- [x] Ship it 📅 2024-01-10 ✅ 2024-01-20".to_string()],
+                true
+            )
+        );
+    }
+
+    #[test]
+    fn toggle_reopens_done_task() {
+        let today = date("2024-01-20").unwrap();
+
+        assert_eq!(
+            toggle_task("  - [x] Ship it ✅ 2024-01-11 #work", today).unwrap(),
+            (vec!["  - [ ] Ship it #work".to_string()], false)
+        );
+    }
+
+    #[test]
+    fn toggle_inserts_next_occurrence() {
+        let today = date("2024-01-20").unwrap();
+
+        assert_eq!(
+            toggle_task(
+                "- [ ] Pay rent 🔁 every month ⏳ 2024-01-25 📅 2024-01-31",
+                today
+            )
+            .unwrap(),
+            (
+                vec![
+                    "- [ ] Pay rent 🔁 every month ⏳ 2024-02-23 📅 2024-02-29".to_string(),
+                    "- [x] Pay rent 🔁 every month ⏳ 2024-01-25 📅 2024-01-31 ✅ 2024-01-20"
+                        .to_string()
+                ],
+                true
+            )
+        );
+    }
+
+    #[test_case(
+        "- [ ] foo 📅 2024-01-01 ^abc",
+        &["- [x] foo 📅 2024-01-01 ✅ 2024-01-20 ^abc"] ;
+        "block id"
+    )]
+    #[test_case(
+        "- [ ] foo 🔁 every day 📅 2024-01-01 ^abc-1",
+        &["- [ ] foo 🔁 every day 📅 2024-01-02", "- [x] foo 🔁 every day 📅 2024-01-01 ✅ 2024-01-20 ^abc-1"] ;
+        "recurring with block id"
+    )]
+    #[test_case(
+        "- [ ] foo 📅 2024-01-01 🔁 every day ^abc",
+        &["- [ ] foo 📅 2024-01-02 🔁 every day", "- [x] foo 📅 2024-01-01 🔁 every day ✅ 2024-01-20 ^abc"] ;
+        "block id after recurrence rule"
+    )]
+    #[test_case(
+        "- [ ] Read x^ref",
+        &["- [x] Read x^ref ✅ 2024-01-20"] ;
+        "caret within a word"
+    )]
+    fn toggle_keeps_block_id_last(line: &str, expected: &[&str]) {
+        let today = date("2024-01-20").unwrap();
+
+        assert_eq!(toggle_task(line, today).unwrap().0, expected);
+    }
+
+    #[test]
+    fn toggle_recurs_from_completion_when_done() {
+        let today = date("2024-01-20").unwrap();
+
+        let (lines, _) = toggle_task(
+            "- [ ] Stretch [repeat:: every day when done] [due:: 2024-01-01]",
+            today,
+        )
+        .unwrap();
+
+        assert_eq!(
+            lines[0],
+            "- [ ] Stretch [repeat:: every day when done] [due:: 2024-01-21]"
+        );
+    }
+
+    #[test]
+    fn formats_new_tasks() {
+        assert_eq!(
+            format_new_task("Ship it", date("2024-01-01"), date("2024-01-02")),
+            "- [ ] Ship it ⏳ 2024-01-01 📅 2024-01-02"
+        );
+    }
+
+    #[test_case("open", ' ', true ; "open includes todo")]
+    #[test_case("open", '/', true ; "open includes in progress")]
+    #[test_case("open", 'x', false ; "open excludes done")]
+    #[test_case("open", '-', false ; "open excludes cancelled")]
+    #[test_case("done", 'X', true ; "done is case insensitive")]
+    #[test_case("in-progress", '/', true ; "named status")]
+    #[test_case("?", '?', true ; "literal symbol")]
+    #[test_case("todo", 'x', false ; "mismatched status")]
+    fn matches_status_filters(filter: &str, symbol: char, expected: bool) {
+        assert_eq!(status_matches(filter, symbol), expected);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{
+    cli_config::{TimestampSettings, Vault},
+    formats::split_frontmatter,
+};
+use anyhow::{bail, Context};
+use chrono::{
+    format::{Item, StrftimeItems},
+    FixedOffset, Local, Utc,
+};
+use std::{fs, path::Path};
+
+/// The current time, formatted according to the settings
+pub fn now(settings: &TimestampSettings) -> anyhow::Result<String> {
+    if StrftimeItems::new(&settings.format).any(|item| item == Item::Error) {
+        bail!("Invalid timestamp format `{}`", settings.format);
+    }
+
+    let formatted = match settings.timezone.to_lowercase().as_str() {
+        "local" => Local::now().format(&settings.format).to_string(),
+        "utc" => Utc::now().format(&settings.format).to_string(),
+        offset => {
+            let offset = parse_offset(offset).with_context(|| {
+                format!(
+                    "Invalid timezone `{}`, expected local, utc or an offset like +02:00",
+                    settings.timezone
+                )
+            })?;
+            Utc::now()
+                .with_timezone(&offset)
+                .format(&settings.format)
+                .to_string()
+        }
+    };
+
+    Ok(formatted)
+}
+
+/// Parse an offset such as `+02:00`, `-0530` or `+1`
+fn parse_offset(offset: &str) -> Option<FixedOffset> {
+    let (sign, rest) = match offset.chars().next()? {
+        '+' => (1, &offset[1..]),
+        '-' => (-1, &offset[1..]),
+        _ => return None,
+    };
+
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Set a top-level frontmatter property, editing the frontmatter line by line so the
+/// rest of the note, and the formatting of other properties, is left untouched
+pub fn set_property(contents: &str, key: &str, value: &str) -> String {
+    let value = serde_yaml::to_string(value)
+        .map(|yaml| yaml.trim_end().to_string())
+        .unwrap_or_else(|_| value.to_string());
+    let property_line = format!("{key}: {value}");
+
+    let Some((frontmatter, body)) = split_frontmatter(contents) else {
+        return format!("---\n{property_line}\n---\n{contents}");
+    };
+
+    let mut lines: Vec<String> = vec![];
+    let mut replaced = false;
+    let mut skipping_value = false;
+
+    for line in frontmatter.lines() {
+        // Drop the rest of a multi-line value belonging to the key being replaced
+        if skipping_value && (line.starts_with([' ', '\t']) || line.starts_with("- ")) {
+            continue;
+        }
+        skipping_value = false;
+
+        let is_key = line
+            .strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with(':'));
+
+        if is_key && !replaced {
+            lines.push(property_line.clone());
+            replaced = true;
+            skipping_value = true;
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+
+    if !replaced {
+        lines.push(property_line);
+    }
+
+    format!("---\n{}\n---\n{body}", lines.join("\n"))
+}
+
+/// Whether the note's frontmatter already has a top-level property
+fn has_property(contents: &str, key: &str) -> bool {
+    split_frontmatter(contents).is_some_and(|(frontmatter, _)| {
+        frontmatter.lines().any(|line| {
+            line.strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        })
+    })
+}
+
+/// Refresh the updated property, and set the created property if the note is new
+pub fn apply(contents: &str, settings: &TimestampSettings, now: &str, is_new: bool) -> String {
+    let mut stamped = contents.to_string();
+
+    if is_new && !has_property(&stamped, &settings.created_key) {
+        stamped = set_property(&stamped, &settings.created_key, now);
+    }
+
+    set_property(&stamped, &settings.updated_key, now)
+}
+
+/// Apply timestamps to the contents of a note about to be written to a vault. The vault's
+/// settings are the ones resolved by `get_current_vault`, so the config isn't read again
+pub fn stamp(vault: &Vault, contents: &str, is_new: bool) -> anyhow::Result<String> {
+    match vault.settings.timestamp_settings() {
+        Some(settings) => Ok(apply(contents, &settings, &now(&settings)?, is_new)),
+        None => Ok(contents.to_string()),
+    }
+}
+
+/// Apply timestamps to a note that has already been written, e.g. by an editor
+pub fn stamp_file(vault: &Vault, path: &Path, is_new: bool) -> anyhow::Result<()> {
+    let Some(settings) = vault.settings.timestamp_settings() else {
+        return Ok(());
+    };
+
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Could not read note `{}`", path.display()))?;
+    let stamped = apply(&contents, &settings, &now(&settings)?, is_new);
+
+    fs::write(path, stamped).with_context(|| format!("Could not write note `{}`", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("body\n", "---\nupdated: now\n---\nbody\n" ; "no frontmatter")]
+    #[test_case("---\ntitle: A\n---\nbody\n", "---\ntitle: A\nupdated: now\n---\nbody\n" ; "new key")]
+    #[test_case("---\nupdated: then\ntitle: A\n---\n", "---\nupdated: now\ntitle: A\n---\n" ; "existing key")]
+    #[test_case("---\nupdated:\n  - a\n  - b\ntitle: A\n---\n", "---\nupdated: now\ntitle: A\n---\n" ; "multi-line value")]
+    #[test_case("---\nupdated_by: me\n---\n", "---\nupdated_by: me\nupdated: now\n---\n" ; "key prefix")]
+    fn set_property_edits_frontmatter(contents: &str, expected: &str) {
+        assert_eq!(set_property(contents, "updated", "now"), expected);
+    }
+
+    #[test]
+    fn set_property_quotes_values_when_needed() {
+        assert_eq!(
+            set_property("", "updated", "2024"),
+            "---\nupdated: '2024'\n---\n"
+        );
+    }
+
+    #[test]
+    fn apply_only_sets_created_for_new_notes() {
+        let settings = TimestampSettings::default();
+
+        assert_eq!(
+            apply("body\n", &settings, "now", true),
+            "---\ncreated: now\nupdated: now\n---\nbody\n"
+        );
+        assert_eq!(
+            apply("body\n", &settings, "now", false),
+            "---\nupdated: now\n---\nbody\n"
+        );
+    }
+
+    #[test_case("+02:00", Some(7200) ; "hours and minutes")]
+    #[test_case("-0530", Some(-19800) ; "without colon")]
+    #[test_case("+1", Some(3600) ; "hours only")]
+    #[test_case("02:00", None ; "missing sign")]
+    #[test_case("+25:00", None ; "out of range")]
+    fn parse_offset_accepts_common_forms(offset: &str, expected: Option<i32>) {
+        assert_eq!(parse_offset(offset).map(|o| o.local_minus_utc()), expected);
+    }
+
+    #[test]
+    fn now_rejects_invalid_formats() {
+        let settings = TimestampSettings {
+            format: "%Q".to_string(),
+            ..Default::default()
+        };
+
+        assert!(now(&settings).is_err());
+    }
+}
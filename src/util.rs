@@ -1,14 +1,21 @@
-use crate::cli_config;
+use crate::{
+    cli_config,
+    error::ObxError,
+    output::Output,
+    vault::{matching_notes, read_notes, VaultNote},
+};
 use anyhow::Context;
 use atty::{is, Stream};
+use dialoguer::{theme::ColorfulTheme, FuzzySelect, Select};
 use libobsidian::{ObsidianNote, Properties};
 use std::{
+    env,
     ffi::OsStr,
     fs,
     path::{Path, PathBuf},
 };
 
-pub type CommandResult = anyhow::Result<Option<String>>;
+pub type CommandResult = anyhow::Result<Option<Output>>;
 
 pub fn resolve_note_path(path_or_string: &str, vault_path: &PathBuf) -> anyhow::Result<PathBuf> {
     let file_path = Path::new(path_or_string);
@@ -24,18 +31,279 @@ pub fn resolve_note_path(path_or_string: &str, vault_path: &PathBuf) -> anyhow::
     Ok(note_path)
 }
 
-pub fn get_current_vault(vault_name_override: Option<String>) -> anyhow::Result<cli_config::Vault> {
+/// Where a note that doesn't exist yet should be created, which is the vault's notes
+/// folder unless the path includes a folder of its own
+pub fn new_note_path(query: &str, vault: &cli_config::Vault) -> anyhow::Result<PathBuf> {
+    // From a folder inside the vault, new notes go where the user is, like any other file
+    if let Some(cwd) = cwd_in_vault(vault).filter(|cwd| !cwd.as_os_str().is_empty()) {
+        return resolve_note_path(query, &vault.path.join(cwd));
+    }
+
+    let has_folder = Path::new(query)
+        .parent()
+        .is_some_and(|parent| !parent.as_os_str().is_empty());
+
+    match &vault.settings.notes_folder {
+        Some(folder) if !has_folder => resolve_note_path(query, &vault.path.join(folder)),
+        _ => resolve_note_path(query, &vault.path),
+    }
+}
+
+/// A note path relative to the working directory, if the working directory is inside the
+/// vault and the note exists there
+fn cwd_note_path(query: &str, vault: &cli_config::Vault) -> Option<PathBuf> {
+    let cwd = cwd_in_vault(vault)?;
+    let candidate = resolve_note_path(query, &vault.path.join(cwd)).ok()?;
+
+    let canonical = fs::canonicalize(&candidate).ok()?;
+    let relative = canonical
+        .strip_prefix(fs::canonicalize(&vault.path).ok()?)
+        .ok()?;
+
+    canonical.is_file().then(|| vault.path.join(relative))
+}
+
+/// Find the note a user means by `query`. A path relative to the working directory or the
+/// vault root is used as-is, otherwise the query is matched against note paths, basenames
+/// and aliases across the vault. If nothing matches, the path a new note would be created
+/// at is returned
+pub fn resolve_note(query: &str, vault: &cli_config::Vault) -> anyhow::Result<PathBuf> {
+    if let Some(path) = cwd_note_path(query, vault) {
+        return Ok(path);
+    }
+
+    let exact_path = resolve_note_path(query, &vault.path)?;
+    if exact_path.exists() {
+        return Ok(exact_path);
+    }
+
+    let notes = read_notes(vault)?;
+    let candidates = matching_notes(query, &notes);
+
+    match candidates.as_slice() {
+        [] => new_note_path(query, vault),
+        [idx] => Ok(notes[*idx].path.clone()),
+        _ => {
+            let names: Vec<String> = candidates
+                .iter()
+                .map(|idx| notes[*idx].relative_path.display().to_string())
+                .collect();
+
+            if !should_enable_interactivity() {
+                anyhow::bail!(ObxError::AmbiguousNote {
+                    query: query.to_string(),
+                    matches: names,
+                });
+            }
+
+            let selection = Select::new()
+                .with_prompt(format!("Which note did you mean by `{query}`?"))
+                .items(&names)
+                .default(0)
+                .interact()
+                .context("couldn't prompt user to choose a note")?;
+
+            Ok(notes[candidates[selection]].path.clone())
+        }
+    }
+}
+
+/// Let the user search for a note by path, alias or contents when none was given
+pub fn interactive_note_picker(vault: &cli_config::Vault) -> anyhow::Result<PathBuf> {
+    if !should_enable_interactivity() {
+        anyhow::bail!("No note given, a note is required when not running interactively");
+    }
+
+    let notes = read_notes(vault)?;
+    if notes.is_empty() {
+        anyhow::bail!("No notes found in {}", vault.path.display());
+    }
+
+    let items: Vec<String> = notes.iter().map(picker_item).collect();
+
+    let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Pick a note")
+        .items(&items)
+        .default(0)
+        .interact()
+        .context("couldn't prompt user to pick a note")?;
+
+    Ok(notes[selection].path.clone())
+}
+
+/// A single line describing a note, in the format `path (aliases) · first line`
+fn picker_item(note: &VaultNote) -> String {
+    let mut item = note.relative_path.display().to_string();
+
+    let aliases = note.aliases();
+    if !aliases.is_empty() {
+        item.push_str(&format!(" ({})", aliases.join(", ")));
+    }
+
+    let preview = note
+        .body()
+        .lines()
+        .map(|line| line.trim_start_matches('#').trim())
+        .find(|line| !line.is_empty());
+
+    if let Some(preview) = preview {
+        let truncated: String = preview.chars().take(60).collect();
+        let ellipsis = if truncated.len() < preview.len() {
+            "…"
+        } else {
+            ""
+        };
+        item.push_str(&format!(" · {truncated}{ellipsis}"));
+    }
+
+    item
+}
+
+/// The folder containing `.obsidian`, nearest to `dir`, for vaults that aren't in the config
+fn find_obsidian_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".obsidian").is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// The registered vault that `dir` is inside, preferring the innermost if vaults are nested
+fn vault_containing(config: &cli_config::Config, dir: &Path) -> Option<cli_config::Vault> {
+    config
+        .vaults
+        .iter()
+        .filter_map(|vault| {
+            let vault_path = fs::canonicalize(&vault.path).ok()?;
+            dir.starts_with(&vault_path)
+                .then_some((vault_path.components().count(), vault))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, vault)| vault.clone())
+}
+
+fn unregistered_vault(path: PathBuf) -> cli_config::Vault {
+    cli_config::Vault {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path,
+        ..Default::default()
+    }
+}
+
+/// The vault commands should act on. In order, that's the one given with `--vault`, the
+/// registered vault the working directory is inside, an unregistered vault (a folder
+/// with `.obsidian`) the working directory is inside, or else the current vault
+pub fn get_current_vault() -> anyhow::Result<cli_config::Vault> {
+    let (vault, defaults) = find_current_vault()?;
+
+    with_settings(vault, &defaults)
+}
+
+/// The vault [`get_current_vault`] would use and the global defaults, before its
+/// `.obx.yml` and the defaults are applied to its settings
+pub fn find_current_vault() -> anyhow::Result<(cli_config::Vault, cli_config::VaultSettings)> {
+    let vault_override = cli_config::vault_override();
+    let cwd = env::current_dir()
+        .ok()
+        .and_then(|cwd| fs::canonicalize(cwd).ok());
+    let local_root = || cwd.as_deref().and_then(find_obsidian_root);
+
+    let (found_vault, defaults) = match cli_config::read() {
+        Ok(config) => {
+            let found_vault = match vault_override {
+                Some(vault_name) => find_vault(&config, &vault_name)?,
+                None => match cwd
+                    .as_deref()
+                    .and_then(|cwd| vault_containing(&config, cwd))
+                {
+                    Some(vault) => vault,
+                    None => match local_root() {
+                        Some(root) => unregistered_vault(root),
+                        None => find_vault(&config, &config.current_vault)?,
+                    },
+                },
+            };
+
+            (found_vault, config.defaults)
+        }
+        // Inside a vault obx can run without any config at all, but a config file that
+        // exists and is broken should still be reported
+        Err(e) => match (vault_override, local_root()) {
+            (None, Some(root)) if !cli_config::exists() => {
+                (unregistered_vault(root), Default::default())
+            }
+            _ => return Err(e),
+        },
+    };
+
+    Ok((found_vault, defaults))
+}
+
+/// Every vault in the config, each with its settings resolved as for [`get_current_vault`]
+pub fn get_all_vaults() -> anyhow::Result<Vec<cli_config::Vault>> {
     let config = cli_config::read()?;
-    let vault_name = vault_name_override.unwrap_or(config.current_vault);
 
-    let found_vault: cli_config::Vault = config
+    config
+        .vaults
+        .into_iter()
+        .map(|vault| with_settings(vault, &config.defaults))
+        .collect()
+}
+
+/// Apply the vault's `.obx.yml`, and then the global defaults, to its settings
+pub fn with_settings(
+    mut vault: cli_config::Vault,
+    defaults: &cli_config::VaultSettings,
+) -> anyhow::Result<cli_config::Vault> {
+    if let Some(local) = cli_config::read_local(&vault.path)? {
+        vault.settings = local.merged_with(&vault.settings);
+    }
+
+    vault.settings = vault.settings.merged_with(defaults);
+
+    Ok(vault)
+}
+
+fn find_vault(config: &cli_config::Config, vault_name: &str) -> anyhow::Result<cli_config::Vault> {
+    config
         .vaults
         .iter()
         .find(|v| v.name == vault_name)
-        .context("Expected to find the current vault in config")?
-        .clone();
+        .cloned()
+        .ok_or_else(|| ObxError::VaultNotFound(vault_name.to_string()).into())
+}
+
+/// The working directory relative to the vault root, when it's inside the vault
+fn cwd_in_vault(vault: &cli_config::Vault) -> Option<PathBuf> {
+    let cwd = fs::canonicalize(env::current_dir().ok()?).ok()?;
+    let vault_path = fs::canonicalize(&vault.path).ok()?;
+
+    cwd.strip_prefix(vault_path).ok().map(Path::to_path_buf)
+}
 
-    Ok(found_vault)
+pub fn obsidian_note_uri(note_path: &PathBuf, vault: String) -> String {
+    format!(
+        "obsidian://open?vault={}&file={}",
+        percent_encode(&vault),
+        percent_encode(&note_path.to_string_lossy())
+    )
+}
+
+/// Percent-encode a URI query value, leaving only the unreserved characters as they are
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    encoded
 }
 
 pub fn should_enable_interactivity() -> bool {
@@ -47,6 +315,16 @@ mod tests {
     use super::*;
     use test_case::test_case;
 
+    #[test_case("main", "/vault/note.md", "obsidian://open?vault=main&file=%2Fvault%2Fnote.md" ; "plain")]
+    #[test_case("my vault", "/vault/a & b#1.md", "obsidian://open?vault=my%20vault&file=%2Fvault%2Fa%20%26%20b%231.md" ; "reserved characters")]
+    #[test_case("main", "/vault/café.md", "obsidian://open?vault=main&file=%2Fvault%2Fcaf%C3%A9.md" ; "unicode")]
+    fn obsidian_note_uri_encodes_query_values(vault: &str, path: &str, expected: &str) {
+        assert_eq!(
+            obsidian_note_uri(&PathBuf::from(path), vault.to_string()),
+            expected
+        );
+    }
+
     #[test_case("foo", "foo.md" ; "plain filename")]
     #[test_case("bar/foo", "bar/foo.md" ; "with path")]
     #[test_case("foo.txt", "foo.txt" ; "with another extension")]
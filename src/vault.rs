@@ -0,0 +1,463 @@
+use crate::{cli_config::Vault, formats::split_frontmatter};
+use anyhow::Context;
+use libobsidian::Properties;
+use regex::Regex;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+use unicode_normalization::UnicodeNormalization;
+
+/// A markdown note found while walking a vault
+#[derive(Debug, Clone)]
+pub struct VaultNote {
+    /// Absolute path to the note
+    pub path: PathBuf,
+    /// Path to the note relative to the vault root
+    pub relative_path: PathBuf,
+    pub contents: String,
+}
+
+impl VaultNote {
+    pub fn read(vault_path: &Path, path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read note `{}`", path.display()))?;
+
+        let relative_path = path.strip_prefix(vault_path).unwrap_or(path).to_path_buf();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            relative_path,
+            contents,
+        })
+    }
+
+    /// The note name as Obsidian displays it, the file name without `.md`
+    pub fn title(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// The parsed frontmatter, or `None` if there isn't any or it isn't valid YAML
+    pub fn properties(&self) -> Option<Properties> {
+        let (frontmatter, _) = split_frontmatter(&self.contents)?;
+        match serde_yaml::from_str(frontmatter) {
+            Ok(serde_yaml::Value::Null) | Err(_) => None,
+            Ok(properties) => Some(properties),
+        }
+    }
+
+    /// The contents of the note with the frontmatter removed
+    pub fn body(&self) -> &str {
+        split_frontmatter(&self.contents)
+            .map(|(_, body)| body)
+            .unwrap_or(&self.contents)
+    }
+
+    /// The number of lines taken up by the frontmatter block, so line numbers within
+    /// `body()` can be mapped back to line numbers within the file
+    pub fn body_line_offset(&self) -> usize {
+        let body_start = self.contents.len() - self.body().len();
+        self.contents[..body_start].matches('\n').count()
+    }
+
+    /// Lines of the body that aren't inside a fenced code block, paired with their
+    /// 1-based line number within the file
+    pub fn lines_outside_code(&self) -> Vec<(usize, &str)> {
+        let offset = self.body_line_offset();
+        let mut in_code_block = false;
+        let mut lines = vec![];
+
+        for (idx, line) in self.body().lines().enumerate() {
+            if is_code_fence(line) {
+                in_code_block = !in_code_block;
+                continue;
+            }
+
+            if !in_code_block {
+                lines.push((offset + idx + 1, line));
+            }
+        }
+
+        lines
+    }
+
+    /// Tags from the `tags` property, without the `#`
+    pub fn property_tags(&self) -> Vec<String> {
+        let Some(properties) = self.properties() else {
+            return vec![];
+        };
+
+        let Some(value) = properties.get("tags").or_else(|| properties.get("tag")) else {
+            return vec![];
+        };
+
+        let raw_tags: Vec<String> = match value {
+            serde_yaml::Value::String(s) => s
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .map(str::to_string)
+                .collect(),
+            serde_yaml::Value::Sequence(seq) => seq
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            _ => vec![],
+        };
+
+        raw_tags
+            .into_iter()
+            .map(|t| t.trim().trim_start_matches('#').to_string())
+            .filter(|t| !t.is_empty())
+            .collect()
+    }
+
+    /// Alternative names for the note from the `aliases` property
+    pub fn aliases(&self) -> Vec<String> {
+        let Some(properties) = self.properties() else {
+            return vec![];
+        };
+
+        let Some(value) = properties
+            .get("aliases")
+            .or_else(|| properties.get("alias"))
+        else {
+            return vec![];
+        };
+
+        let aliases: Vec<String> = match value {
+            serde_yaml::Value::String(s) => s.split(',').map(str::to_string).collect(),
+            serde_yaml::Value::Sequence(seq) => seq
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            _ => vec![],
+        };
+
+        aliases
+            .into_iter()
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .collect()
+    }
+
+    /// The number of words in the body, ignoring anything without a letter or number
+    /// such as list markers
+    pub fn word_count(&self) -> usize {
+        self.body()
+            .split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphanumeric))
+            .count()
+    }
+
+    /// The number of headings in the body, ignoring any inside code blocks
+    pub fn heading_count(&self) -> usize {
+        self.lines_outside_code()
+            .into_iter()
+            .filter(|(_, line)| heading(line).is_some())
+            .count()
+    }
+
+    /// Tags from the `tags` property and any inline `#tags` in the body, without the `#`
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = self.property_tags();
+
+        for (_, line) in self.lines_outside_code() {
+            for tag in inline_tags(line) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+
+        tags
+    }
+}
+
+/// Normalise a note name for comparison, so differences in case or in how accented
+/// characters are encoded don't stop it matching
+pub fn normalize_name(name: &str) -> String {
+    let normalized = name
+        .trim()
+        .replace('\\', "/")
+        .nfc()
+        .collect::<String>()
+        .to_lowercase();
+
+    match normalized.strip_suffix(".md") {
+        Some(stem) => stem.to_string(),
+        None => normalized,
+    }
+}
+
+/// The notes that `query` could refer to, the way Obsidian resolves links. Paths and
+/// basenames are preferred, and aliases are only considered if neither match
+pub fn matching_notes(query: &str, notes: &[VaultNote]) -> Vec<usize> {
+    let query = normalize_name(query);
+    let suffix = format!("/{query}");
+
+    let by_path: Vec<usize> = (0..notes.len())
+        .filter(|idx| {
+            let path = normalize_name(&notes[*idx].relative_path.to_string_lossy());
+            path == query || path.ends_with(&suffix)
+        })
+        .collect();
+
+    if !by_path.is_empty() {
+        return by_path;
+    }
+
+    (0..notes.len())
+        .filter(|idx| {
+            notes[*idx]
+                .aliases()
+                .iter()
+                .any(|alias| normalize_name(alias) == query)
+        })
+        .collect()
+}
+
+/// Find inline `#tags` within a line of text, without the leading `#`
+pub fn inline_tags(text: &str) -> Vec<String> {
+    static TAG_RE: OnceLock<Regex> = OnceLock::new();
+    let re = TAG_RE.get_or_init(|| {
+        Regex::new(r"(?:^|\s)#([\p{L}\p{N}_/-]+)").expect("tag regex should compile")
+    });
+
+    re.captures_iter(text)
+        .map(|c| c[1].to_string())
+        // Obsidian doesn't treat purely numeric strings like #123 as tags
+        .filter(|t| !t.chars().all(|c| c.is_ascii_digit()))
+        .collect()
+}
+
+/// Whether `tag` matches the `filter`, either exactly or as a parent of a nested tag
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    let tag = tag.to_lowercase();
+    let filter = filter.trim_start_matches('#').to_lowercase();
+
+    tag == filter || tag.starts_with(&format!("{filter}/"))
+}
+
+pub fn is_code_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// The level and text of a markdown heading such as `## Heading`, or `None` if the
+/// line isn't a heading
+pub fn heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let text = &trimmed[level..];
+
+    ((1..=6).contains(&level) && text.starts_with(char::is_whitespace))
+        .then(|| (level, text.trim()))
+}
+
+/// Compile a gitignore-style glob into a regex matched against vault-relative paths.
+/// Patterns without a `/` match a file or folder name at any depth, and a pattern
+/// matching a folder also matches everything inside it
+fn glob_regex(pattern: &str) -> Regex {
+    let pattern = pattern.trim().trim_end_matches('/');
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+
+    let mut regex = String::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    let prefix = if anchored { "^" } else { "^(?:.*/)?" };
+    Regex::new(&format!("{prefix}{regex}(?:/.*)?$")).expect("escaped glob should compile")
+}
+
+/// Recursively find every file within the vault, skipping hidden files and folders
+/// such as `.obsidian` and `.trash`, and anything matching the vault's ignore patterns
+pub fn list_files(vault: &Vault) -> anyhow::Result<Vec<PathBuf>> {
+    let ignore: Vec<Regex> = vault
+        .settings
+        .ignore
+        .iter()
+        .map(|pattern| glob_regex(pattern))
+        .collect();
+
+    let mut files = vec![];
+    let mut dirs = vec![vault.path.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir)
+            .with_context(|| format!("Could not read directory `{}`", dir.display()))?;
+
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+
+            let relative_path = path
+                .strip_prefix(&vault.path)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            if ignore.iter().any(|re| re.is_match(&relative_path)) {
+                continue;
+            }
+
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+pub fn is_note(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+}
+
+/// Recursively find every markdown note within the vault, see [`list_files`]
+pub fn list_notes(vault: &Vault) -> anyhow::Result<Vec<PathBuf>> {
+    Ok(list_files(vault)?
+        .into_iter()
+        .filter(|path| is_note(path))
+        .collect())
+}
+
+/// Read a note, printing a warning and returning `None` if it can't be read, so one
+/// unreadable or non UTF-8 file doesn't stop a command working across the whole vault
+pub fn read_note_or_warn(vault_path: &Path, path: &Path) -> Option<VaultNote> {
+    match VaultNote::read(vault_path, path) {
+        Ok(note) => Some(note),
+        Err(e) => {
+            eprintln!("Warning: {e:#}, skipping it");
+            None
+        }
+    }
+}
+
+/// Read every markdown note within the vault, skipping any that can't be read, see
+/// [`read_note_or_warn`]
+pub fn read_notes(vault: &Vault) -> anyhow::Result<Vec<VaultNote>> {
+    Ok(list_notes(vault)?
+        .iter()
+        .filter_map(|path| read_note_or_warn(&vault.path, path))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn note(contents: &str) -> VaultNote {
+        VaultNote {
+            path: PathBuf::from("/vault/note.md"),
+            relative_path: PathBuf::from("note.md"),
+            contents: contents.to_string(),
+        }
+    }
+
+    #[test_case("#tag", vec!["tag"] ; "single tag")]
+    #[test_case("some text #one and #two", vec!["one", "two"] ; "multiple tags")]
+    #[test_case("#nested/tag", vec!["nested/tag"] ; "nested tag")]
+    #[test_case("# Heading", vec![] ; "heading")]
+    #[test_case("issue #123", vec![] ; "numeric")]
+    #[test_case("a#notatag", vec![] ; "mid-word hash")]
+    fn inline_tags_finds_tags(input: &str, expected: Vec<&str>) {
+        assert_eq!(inline_tags(input), expected);
+    }
+
+    #[test_case("project", "project", true ; "exact")]
+    #[test_case("project/alpha", "project", true ; "nested")]
+    #[test_case("Project", "#project", true ; "case and hash insensitive")]
+    #[test_case("projects", "project", false ; "prefix without separator")]
+    fn tag_matches_nested_tags(tag: &str, filter: &str, expected: bool) {
+        assert_eq!(tag_matches(tag, filter), expected);
+    }
+
+    #[test_case("templates", "templates/daily.md", true ; "folder name")]
+    #[test_case("templates/", "notes/templates/daily.md", true ; "nested folder name")]
+    #[test_case("*.excalidraw.md", "drawings/plan.excalidraw.md", true ; "extension at any depth")]
+    #[test_case("archive/*.md", "archive/old.md", true ; "anchored")]
+    #[test_case("archive/*.md", "notes/archive/old.md", false ; "anchored elsewhere")]
+    #[test_case("archive/**/draft.md", "archive/2023/q1/draft.md", true ; "double star")]
+    #[test_case("draft?.md", "draft1.md", true ; "single character")]
+    #[test_case("templates", "templates-old/note.md", false ; "name prefix")]
+    fn glob_regex_matches_paths(pattern: &str, path: &str, expected: bool) {
+        assert_eq!(glob_regex(pattern).is_match(path), expected);
+    }
+
+    #[test]
+    fn lines_outside_code_skips_frontmatter_and_fences() {
+        let note = note("---\ntags: [a]\n---\nfirst\n```\ncode\n```\nlast\n");
+
+        assert_eq!(note.lines_outside_code(), vec![(4, "first"), (8, "last")]);
+    }
+
+    #[test_case("---\naliases: [One, Two]\n---\n", vec!["One", "Two"] ; "list")]
+    #[test_case("---\naliases: One, Two\n---\n", vec!["One", "Two"] ; "comma separated")]
+    #[test_case("---\nalias: One\n---\n", vec!["One"] ; "singular key")]
+    #[test_case("no frontmatter", vec![] ; "no frontmatter")]
+    fn aliases_reads_property(contents: &str, expected: Vec<&str>) {
+        assert_eq!(note(contents).aliases(), expected);
+    }
+
+    #[test_case("Cafe\u{301}.md", "café" ; "unicode form")]
+    #[test_case("Note.MD", "note" ; "uppercase extension")]
+    #[test_case("Folder\\Note", "folder/note" ; "windows separators")]
+    fn normalize_name_ignores_case_and_unicode_form(name: &str, expected: &str) {
+        assert_eq!(normalize_name(name), expected);
+    }
+
+    #[test_case("note", vec![0, 1] ; "basename")]
+    #[test_case("FOLDER/NOTE", vec![1] ; "path")]
+    #[test_case("other.md", vec![2] ; "with extension")]
+    #[test_case("nickname", vec![2] ; "alias")]
+    #[test_case("missing", vec![] ; "no match")]
+    fn matching_notes_finds_paths_then_aliases(query: &str, expected: Vec<usize>) {
+        let vault_note = |path: &str, contents: &str| VaultNote {
+            path: PathBuf::from("/vault").join(path),
+            relative_path: PathBuf::from(path),
+            contents: contents.to_string(),
+        };
+
+        let notes = vec![
+            vault_note("note.md", ""),
+            vault_note("folder/note.md", ""),
+            vault_note("other.md", "---\naliases: [nickname, note]\n---\n"),
+        ];
+
+        assert_eq!(matching_notes(query, &notes), expected);
+    }
+
+    #[test]
+    fn tags_combines_properties_and_inline() {
+        let note = note("---\ntags:\n  - '#one'\n  - two\n---\nbody #three\n");
+
+        assert_eq!(note.tags(), vec!["one", "two", "three"]);
+    }
+}
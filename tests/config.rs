@@ -0,0 +1,455 @@
+use assert_fs::prelude::*;
+use indoc::indoc;
+mod utils;
+use predicates::prelude::*;
+use utils::*;
+
+mod config {
+    use super::*;
+
+    mod get {
+        use super::*;
+
+        #[test]
+        fn prints_top_level_values() {
+            Obx::from_command("config get current_vault").assert_stdout("main\n");
+        }
+
+        #[test]
+        fn follows_dotted_keys_into_lists() {
+            Obx::from_command("config get vaults.1.name").assert_stdout("secondary\n");
+        }
+
+        #[test]
+        fn prints_sections_as_yaml() {
+            Obx::from_command("config get timestamps")
+                .with_extra_config(indoc! {r#"
+                    timestamps:
+                      enabled: false
+                "#})
+                .assert_stdout(indoc! {r#"
+                    enabled: false
+                    created_key: created
+                    updated_key: updated
+                    format: '%Y-%m-%dT%H:%M:%S'
+                    timezone: local
+                "#});
+        }
+
+        #[test]
+        fn fails_for_unknown_keys() {
+            Obx::from_command("config get nope").assert_stderr("Unknown config key `nope`\n");
+        }
+    }
+
+    mod set {
+        use super::*;
+
+        #[test]
+        fn sets_values() {
+            let cmd = Obx::from_command("config set editor nvim");
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+
+            let _ = &cmd.assert_stdout("Set editor to nvim\n");
+
+            config_file.assert(predicate::str::contains("\neditor: nvim\n"));
+        }
+
+        #[test]
+        fn keeps_key_order_and_unknown_keys() {
+            let cmd = Obx::from_command("config set current_vault other");
+            let cmd = cmd.with_config_file(indoc! {"
+                version: 1
+                vaults:
+                - name: other
+                  path: /some/path
+                plugin_setting: keep me
+                current_vault: main
+            "});
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+
+            let _ = &cmd.assert_stdout("Set current_vault to other\n");
+
+            config_file.assert(predicate::str::diff(indoc! {"
+                version: 1
+                vaults:
+                - name: other
+                  path: /some/path
+                plugin_setting: keep me
+                current_vault: other
+            "}));
+        }
+
+        #[test]
+        fn creates_missing_sections() {
+            let cmd = Obx::from_command("config set timestamps.timezone utc");
+            let config = default_config(&cmd.temp_dir).replace("timestamps: false\n", "");
+            let cmd = cmd.with_config_file(&config);
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+
+            let _ = &cmd.assert_stdout("Set timestamps.timezone to utc\n");
+
+            config_file.assert(
+                predicate::str::contains("timestamps:\n")
+                    .and(predicate::str::contains("  timezone: utc\n")),
+            );
+        }
+
+        #[test]
+        fn rejects_invalid_values() {
+            let mut cmd = Obx::from_command("config set vaults nope");
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+            let original = default_config(&cmd.temp_dir);
+
+            cmd.cmd
+                .assert()
+                .failure()
+                .stderr(predicate::str::starts_with("Invalid config: "));
+
+            config_file.assert(predicate::str::diff(original));
+        }
+
+        #[test]
+        fn rejects_unknown_keys() {
+            Obx::from_command("config set nope value").assert_stderr("Unknown config key `nope`\n");
+        }
+    }
+
+    mod unset {
+        use super::*;
+
+        #[test]
+        fn removes_values() {
+            let cmd = Obx::from_command("config unset editor").with_extra_config("editor: nvim\n");
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+
+            let _ = &cmd.assert_stdout("Unset editor\n");
+
+            config_file.assert(predicate::str::contains("editor").not());
+        }
+
+        #[test]
+        fn rejects_removing_required_values() {
+            let mut cmd = Obx::from_command("config unset current_vault");
+
+            cmd.cmd
+                .assert()
+                .failure()
+                .stderr(predicate::str::starts_with(
+                    "Invalid config: missing field `current_vault`",
+                ));
+        }
+    }
+
+    mod edit {
+        use super::*;
+
+        #[test]
+        fn saves_valid_changes() {
+            let cmd = Obx::from_command("config edit").with_editor(
+                r#"sed -i.bak 's/current_vault: main/current_vault: secondary/' "$1""#,
+            );
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+
+            let _ = &cmd.assert_stdout_contains("Saved config ");
+
+            config_file.assert(predicate::str::starts_with(
+                "version: 1\ncurrent_vault: secondary\n",
+            ));
+        }
+
+        #[test]
+        fn saves_the_file_as_it_was_edited() {
+            let cmd = Obx::from_command("config edit")
+                .with_editor(r#"printf '# Set by hand\neditor: nvim\n' >> "$1""#);
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+            let expected = format!(
+                "{}# Set by hand\neditor: nvim\n",
+                default_config(&cmd.temp_dir)
+            );
+
+            let _ = &cmd.assert_stdout_contains("Saved config ");
+
+            config_file.assert(predicate::str::diff(expected));
+        }
+
+        #[test]
+        fn reports_unchanged_config() {
+            Obx::from_command("config edit").assert_stdout("No changes to config\n");
+        }
+
+        #[test]
+        fn leaves_config_untouched_when_invalid() {
+            let mut cmd =
+                Obx::from_command("config edit").with_editor(r#"echo "vaults: nope" >> "$1""#);
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+            let original = default_config(&cmd.temp_dir);
+
+            cmd.cmd
+                .assert()
+                .failure()
+                .stderr(predicate::str::starts_with("Invalid config: "));
+
+            config_file.assert(predicate::str::diff(original));
+        }
+    }
+
+    #[test]
+    fn editor_setting_is_used_instead_of_env() {
+        let cmd = Obx::from_command("notes edit simple-note")
+            .with_editor("exit 1")
+            .with_extra_config("editor: sh -c 'printf \"\\nfrom-config\\n\" >> \"$0\"'\n");
+        let note = cmd.temp_dir.child("main-vault/simple-note.md");
+
+        let _ = &cmd.assert_stdout("Saved changes to simple-note.md (+1 -0)\n");
+
+        note.assert(predicate::str::ends_with("from-config\n"));
+    }
+
+    mod vault_settings {
+        use super::*;
+
+        #[test]
+        fn print_shows_settings_merged_into_each_vault() {
+            Obx::from_command("config print")
+                .with_extra_config("notes_folder: inbox\n")
+                .assert_stdout_contains("  path: ")
+                .assert_stdout_contains(
+                    "  notes_folder: inbox\n  timestamps: false\n- name: secondary",
+                );
+        }
+
+        #[test]
+        fn print_shows_nested_vault_settings() {
+            Obx::from_command("config print")
+                .with_extra_config(indoc! {r#"
+                    timestamps:
+                      timezone: utc
+                "#})
+                .assert_stdout_contains("  timestamps:\n")
+                .assert_stdout_contains("    timezone: utc\n- name: secondary");
+        }
+
+        #[test]
+        fn creates_notes_in_notes_folder() {
+            Obx::from_command("notes create new-note")
+                .with_editor(r#"echo "This was appended by \$EDITOR" >> "$1""#)
+                .with_extra_config("notes_folder: inbox\n")
+                .assert_created("main-vault/inbox/new-note.md");
+        }
+
+        #[test]
+        fn vault_settings_override_defaults() {
+            let cmd = Obx::from_command("notes create new-note")
+                .with_editor(r#"echo "This was appended by \$EDITOR" >> "$1""#);
+            let config = format!(
+                indoc! {r#"
+                    current_vault: main
+                    vaults:
+                    - name: main
+                      path: {dir}/main-vault/
+                      notes_folder: drafts
+                    notes_folder: inbox
+                "#},
+                dir = cmd.temp_dir.display()
+            );
+
+            cmd.with_config_file(&config)
+                .assert_created("main-vault/drafts/new-note.md");
+        }
+
+        #[test]
+        fn skips_ignored_paths() {
+            Obx::from_command("tasks list --status ?")
+                .with_extra_config("ignore:\n- folder/\n")
+                .assert_stdout("No tasks found\n");
+        }
+
+        #[test]
+        fn uses_output_format_when_no_format_is_given() {
+            Obx::from_command("graph stats")
+                .with_extra_config("output_format: json\n")
+                .assert_stdout_contains(r#"{"notes":"#);
+        }
+
+        #[test]
+        fn rejects_output_formats_commands_cannot_print_in() {
+            let mut cmd =
+                Obx::from_command("graph stats").with_extra_config("output_format: dot\n");
+
+            cmd.cmd.assert().code(5).stderr(predicate::str::contains(
+                "`dot` is not an output format, expected one of pretty, json",
+            ));
+        }
+    }
+
+    mod overrides {
+        use super::*;
+
+        #[test]
+        fn env_vars_override_config_values() {
+            let mut cmd = Obx::from_command("vaults path");
+            let vault_path = format!("{}/another/path\n", cmd.temp_dir.display());
+            cmd.env("OBX_CURRENT_VAULT", "secondary");
+
+            cmd.assert_stdout(vault_path);
+        }
+
+        #[test]
+        fn env_vars_override_list_items() {
+            let mut cmd = Obx::from_command("vaults path --vault secondary");
+            cmd.env("OBX_VAULTS__1__PATH", "/elsewhere");
+
+            cmd.assert_stdout("/elsewhere\n");
+        }
+
+        #[test]
+        fn env_vars_are_not_saved() {
+            let mut cmd = Obx::from_command("config set editor nvim");
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+            cmd.env("OBX_CURRENT_VAULT", "secondary");
+
+            let _ = &cmd.assert_success();
+
+            config_file.assert(predicate::str::starts_with(
+                "version: 1\ncurrent_vault: main\n",
+            ));
+        }
+
+        #[test]
+        fn runs_without_a_config_file() {
+            let mut cmd = Obx::from_command("notes path simple-note");
+            let vault_path = cmd.temp_dir.child("main-vault");
+            let note_path = vault_path.child("simple-note.md");
+            let empty_config_dir = cmd.temp_dir.child("empty-config");
+
+            cmd.env("OBX_CONFIG_DIR", empty_config_dir.display().to_string())
+                .env("OBX_CURRENT_VAULT", "ci")
+                .env("OBX_VAULTS__0__NAME", "ci")
+                .env("OBX_VAULTS__0__PATH", vault_path.display().to_string());
+
+            cmd.assert_stdout(format!("{}\n", note_path.display()));
+        }
+
+        #[test]
+        fn config_flag_reads_another_file() {
+            let cmd = Obx::from_command("vaults path --config custom.yml");
+            let custom_config = default_config(&cmd.temp_dir)
+                .replace("current_vault: main", "current_vault: secondary");
+            cmd.temp_dir
+                .child("custom.yml")
+                .write_str(&custom_config)
+                .unwrap();
+            let vault_path = format!("{}/another/path\n", cmd.temp_dir.display());
+
+            cmd.assert_stdout(vault_path);
+        }
+
+        #[test]
+        fn vault_flag_works_for_every_command_group() {
+            let cmd = Obx::from_command("vaults path --vault secondary");
+            let vault_path = format!("{}/another/path\n", cmd.temp_dir.display());
+
+            cmd.assert_stdout(vault_path);
+        }
+    }
+
+    mod migrate {
+        use super::*;
+
+        const UNVERSIONED: &str = "current_vault: main\nvaults: []\n";
+
+        #[test]
+        fn upgrades_old_configs_when_read() {
+            let cmd = Obx::from_command("config get current_vault").with_config_file(UNVERSIONED);
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+            let backup_file = cmd.temp_dir.child("config/obx/config.yml.bak");
+
+            let _ = &cmd.assert_stdout("main\n");
+
+            config_file.assert("version: 1\ncurrent_vault: main\nvaults: []\n");
+            backup_file.assert(UNVERSIONED);
+        }
+
+        #[test]
+        fn keeps_comments() {
+            let commented = "# Shared with the laptop\ncurrent_vault: main # for now\nvaults: []\n";
+            let cmd = Obx::from_command("config get current_vault").with_config_file(commented);
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+
+            let _ = &cmd.assert_stdout("main\n");
+
+            config_file.assert(format!("version: 1\n{commented}"));
+        }
+
+        #[test]
+        fn migrates_in_memory_when_the_file_cant_be_changed() {
+            let mut cmd =
+                Obx::from_command("config get current_vault").with_config_file(UNVERSIONED);
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+            // A directory in the way of the backup stops the file being migrated
+            cmd.temp_dir
+                .child("config/obx/config.yml.bak")
+                .create_dir_all()
+                .unwrap();
+
+            cmd.cmd
+                .assert()
+                .success()
+                .stdout("main\n")
+                .stderr(predicate::str::starts_with(
+                    "Warning: Could not migrate config file",
+                ));
+
+            config_file.assert(UNVERSIONED);
+        }
+
+        #[test]
+        fn dry_run_leaves_config_untouched() {
+            let cmd = Obx::from_command("config migrate --dry-run").with_config_file(UNVERSIONED);
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+            let backup_file = cmd.temp_dir.child("config/obx/config.yml.bak");
+
+            let _ = &cmd.assert_stdout(indoc! {r#"
+                Would migrate config from version 0 to 1:
+                  1: Add a `version` key
+
+                version: 1
+                current_vault: main
+                vaults: []
+            "#});
+
+            config_file.assert(UNVERSIONED);
+            backup_file.assert(predicate::path::missing());
+        }
+
+        #[test]
+        fn migrates_in_place() {
+            let cmd = Obx::from_command("config migrate").with_config_file(UNVERSIONED);
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+            let backup_file = cmd.temp_dir.child("config/obx/config.yml.bak");
+
+            let _ = &cmd.assert_stdout_contains("Migrated config from version 0 to 1:\n");
+
+            config_file.assert(predicate::str::starts_with("version: 1\n"));
+            backup_file.assert(UNVERSIONED);
+        }
+
+        #[test]
+        fn reports_current_configs() {
+            Obx::from_command("config migrate").assert_stdout("Config is already at version 1\n");
+        }
+
+        #[test]
+        fn warns_about_unknown_keys() {
+            let mut cmd =
+                Obx::from_command("config get current_vault").with_extra_config("colour: blue\n");
+
+            cmd.cmd
+                .assert()
+                .success()
+                .stdout("main\n")
+                .stderr("Warning: Ignoring unknown config key `colour`\n");
+        }
+    }
+}
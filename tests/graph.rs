@@ -0,0 +1,287 @@
+use assert_fs::prelude::*;
+use indoc::indoc;
+mod utils;
+use predicates::prelude::*;
+use serde_json::json;
+use utils::*;
+
+mod graph {
+    use super::*;
+
+    mod export {
+        use super::*;
+
+        #[test]
+        fn exports_dot() {
+            Obx::from_command("graph export --format dot --from complex-note").assert_stdout(
+                indoc! {r#"
+                digraph vault {
+                  "complex-note" [label="complex-note", "some-property"="a string val"];
+                  "hub-note" [label="hub-note", tags="hub"];
+                  "simple-note" [label="simple-note"];
+                  "complex-note" -> "simple-note" [kind="link"];
+                  "hub-note" -> "simple-note" [kind="link"];
+                  "hub-note" -> "complex-note" [kind="link"];
+                }
+            "#},
+            );
+        }
+
+        #[test]
+        fn exports_json_with_embeds() {
+            let stdout_match = &json!({
+                "nodes": [
+                    {
+                        "id": "hub-note",
+                        "title": "hub-note",
+                        "path": "hub-note.md",
+                        "tags": ["hub"],
+                        "properties": {"tags": ["hub"]}
+                    },
+                    {
+                        "id": "with-fm-properties",
+                        "title": "with-fm-properties",
+                        "path": "with-fm-properties.md",
+                        "tags": [],
+                        "properties": {
+                            "test-number": 100,
+                            "test-str": "a string val",
+                            "test-checkbox": true,
+                            "test-list": ["One", "Two"]
+                        }
+                    }
+                ],
+                "edges": [
+                    {"source": "hub-note", "target": "with-fm-properties", "kind": "embed"}
+                ]
+            });
+
+            Obx::from_command("graph export --format json --from with-fm-properties")
+                .assert_stdout(format!("{stdout_match}\n"));
+        }
+
+        #[test]
+        fn filters_by_folder() {
+            let stdout_match = &json!({
+                "nodes": [
+                    {
+                        "id": "folder/child-note",
+                        "title": "child-note",
+                        "path": "folder/child-note.md",
+                        "tags": [],
+                        "properties": {}
+                    },
+                    {
+                        "id": "folder/project-tasks",
+                        "title": "project-tasks",
+                        "path": "folder/project-tasks.md",
+                        "tags": ["planning"],
+                        "properties": {}
+                    }
+                ],
+                "edges": []
+            });
+
+            Obx::from_command("graph export --format json --folder folder")
+                .assert_stdout(format!("{stdout_match}\n"));
+        }
+
+        #[test]
+        fn fails_without_a_format() {
+            Obx::from_command("graph export").assert_stderr(
+                "Choose a format to export the graph as with --format dot, graphml or json\n",
+            );
+        }
+
+        #[test]
+        fn filters_by_tag() {
+            Obx::from_command("graph export --format dot --tag hub").assert_stdout(indoc! {r#"
+                digraph vault {
+                  "hub-note" [label="hub-note", tags="hub"];
+                }
+            "#});
+        }
+
+        #[test]
+        fn exports_graphml_to_file() {
+            let cmd = Obx::from_command(
+                "graph export --format graphml --from with-fm-properties --output graph.graphml",
+            );
+            let graph_file = cmd.temp_dir.child("graph.graphml");
+
+            let _ = &cmd.assert_stdout("Exported 2 notes to graph.graphml\n");
+
+            graph_file.assert(
+                predicate::str::starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#)
+                    .and(predicate::str::contains(
+                        r#"<key id="p0" for="node" attr.name="test-checkbox" attr.type="string"/>"#,
+                    ))
+                    .and(predicate::str::contains(indoc! {r#"
+                        <edge source="hub-note" target="with-fm-properties">
+                              <data key="kind">embed</data>
+                            </edge>
+                    "#})),
+            );
+        }
+
+        #[test]
+        fn fails_for_unknown_seed_note() {
+            Obx::from_command("graph export --format json --from does-not-exist")
+                .assert_stderr("Could not find note `does-not-exist`\n");
+        }
+    }
+
+    mod analytics {
+        use super::*;
+
+        #[test]
+        fn prints_stats() {
+            Obx::from_command("graph stats").assert_stdout(indoc! {"
+                ┌───────────────────┬───────┐
+                │ Metric            │ Value │
+                ├───────────────────┼───────┤
+                │ notes             │ 11    │
+                │ links             │ 5     │
+                │ orphans           │ 6     │
+                │ dead ends         │ 3     │
+                │ components        │ 7     │
+                │ largest component │ 5     │
+                └───────────────────┴───────┘
+            "});
+        }
+
+        #[test]
+        fn prints_stats_as_json() {
+            let stdout_match = &json!({
+                "notes": 4,
+                "links": 1,
+                "orphans": 2,
+                "dead_ends": 1,
+                "components": 3,
+                "largest_component": 2
+            });
+
+            Obx::from_command("graph stats -f json --vault secondary")
+                .assert_stdout(format!("{stdout_match}\n"));
+        }
+
+        #[test]
+        fn prints_orphans() {
+            Obx::from_command("graph orphans").assert_stdout(indoc! {"
+                ┌──────────────────────┐
+                │ Note                 │
+                ├──────────────────────┤
+                │ empty-note           │
+                │ folder/project-tasks │
+                │ html                 │
+                │ link-types           │
+                │ table                │
+                │ tasks                │
+                └──────────────────────┘
+            "});
+        }
+
+        #[test]
+        fn prints_dead_ends_as_json() {
+            let stdout_match = &json!(["folder/child-note", "simple-note", "with-fm-properties"]);
+
+            Obx::from_command("graph dead-ends -f json").assert_stdout(format!("{stdout_match}\n"));
+        }
+
+        #[test]
+        fn prints_message_when_nothing_found() {
+            let cmd = Obx::from_command("graph dead-ends --vault secondary");
+            // Without standup, the only note that's linked to, nothing is a dead end
+            std::fs::remove_file(cmd.temp_dir.child("another/path/standup.md")).unwrap();
+
+            cmd.assert_stdout("No dead ends found\n");
+        }
+
+        #[test]
+        fn prints_orphans_as_json() {
+            let stdout_match = &json!(["deadline", "from-another-vault"]);
+
+            Obx::from_command("graph orphans -f json --vault secondary")
+                .assert_stdout(format!("{stdout_match}\n"));
+        }
+
+        #[test]
+        fn prints_hubs_ranked_by_backlinks_and_pagerank() {
+            Obx::from_command("graph hubs --limit 2").assert_stdout(indoc! {"
+                ┌──────────────┬───────────┬──────────┐
+                │ Note         │ Backlinks │ PageRank │
+                ├──────────────┼───────────┼──────────┤
+                │ simple-note  │ 2         │ 0.1741   │
+                │ complex-note │ 1         │ 0.0941   │
+                └──────────────┴───────────┴──────────┘
+            "});
+        }
+
+        #[test]
+        fn prints_components_as_json() {
+            let stdout_match = &json!([
+                [
+                    "complex-note",
+                    "folder/child-note",
+                    "hub-note",
+                    "simple-note",
+                    "with-fm-properties"
+                ],
+                ["empty-note"],
+                ["folder/project-tasks"],
+                ["html"],
+                ["link-types"],
+                ["table"],
+                ["tasks"]
+            ]);
+
+            Obx::from_command("graph components -f json")
+                .assert_stdout(format!("{stdout_match}\n"));
+        }
+
+        #[test]
+        fn prints_shortest_path() {
+            Obx::from_command("graph path hub-note simple-note")
+                .assert_stdout("hub-note -> simple-note\n");
+        }
+
+        #[test]
+        fn follows_backlinks_when_undirected() {
+            let stdout_match = &json!({
+                "from": "simple-note",
+                "to": "folder/child-note",
+                "length": 2,
+                "path": ["simple-note", "hub-note", "folder/child-note"]
+            });
+
+            Obx::from_command("graph path simple-note child-note --undirected -f json")
+                .assert_stdout(format!("{stdout_match}\n"));
+        }
+
+        #[test]
+        fn resolves_notes_by_alias() {
+            let mut cmd = Obx::from_command("graph path journal --vault secondary");
+            cmd.cmd.arg("daily sync");
+
+            cmd.assert_stdout("journal -> standup\n");
+        }
+
+        #[test]
+        fn fails_when_a_note_is_ambiguous() {
+            Obx::from_command("graph path journal planning --vault secondary").assert_error(
+                8,
+                indoc! {"
+                    Note `planning` is ambiguous, it could be any of:
+                      deadline.md
+                      standup.md
+                "},
+            );
+        }
+
+        #[test]
+        fn fails_when_there_is_no_path() {
+            Obx::from_command("graph path simple-note hub-note")
+                .assert_stderr("No path found from `simple-note` to `hub-note`\n");
+        }
+    }
+}
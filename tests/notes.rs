@@ -45,7 +45,7 @@ mod notes {
         #[test]
         fn fails_for_missing_files() {
             Obx::from_command("notes view does-not-exist.md")
-                .assert_stderr("Could not read note `does-not-exist.md`\n");
+                .assert_error(3, "Could not find note `does-not-exist.md`\n");
         }
     }
 
@@ -103,17 +103,22 @@ mod notes {
 
         #[test]
         fn creates_new_note_file() {
-            Obx::from_command("notes create new-note.md").assert_created("main-vault/new-note.md");
+            Obx::from_command("notes create new-note.md")
+                .with_editor(r#"echo "This was appended by \$EDITOR" >> "$1""#)
+                .assert_created("main-vault/new-note.md");
         }
 
         #[test]
         fn accepts_without_extension() {
-            Obx::from_command("notes create new-note").assert_created("main-vault/new-note.md");
+            Obx::from_command("notes create new-note")
+                .with_editor(r#"echo "This was appended by \$EDITOR" >> "$1""#)
+                .assert_created("main-vault/new-note.md");
         }
 
         #[test]
         fn creates_missing_paths() {
             Obx::from_command("notes create some/nested/folder/new-note.md")
+                .with_editor(r#"echo "This was appended by \$EDITOR" >> "$1""#)
                 .assert_created("main-vault/some/nested/folder/new-note.md");
         }
 
@@ -158,17 +163,89 @@ mod notes {
             created_file.assert(predicate::str::diff(file_content));
         }
 
+        #[test]
+        fn multiline_stdin_content_present_in_file_body() {
+            let stdin_content = indoc! {"
+                # Piped note
+
+                The first line isn't the only one that's kept
+            "};
+            let cmd = Obx::from_command("notes create multiline-note.md");
+
+            let mut wrapped_cmd = assert_cmd::Command::from(cmd.cmd);
+            wrapped_cmd.write_stdin(stdin_content);
+
+            let created_file = &cmd.temp_dir.child("main-vault/multiline-note.md");
+
+            let _ = &wrapped_cmd.assert().success();
+
+            created_file.assert(predicate::str::diff(stdin_content));
+        }
+
+        #[test]
+        fn markdown_stdin_frontmatter_preserved() {
+            let stdin_content = indoc! {"
+                ---
+                title: Piped note
+                tags:
+                - test
+                ---
+                The body of the note
+            "};
+            let cmd = Obx::from_command("notes create markdown-frontmatter-note.md");
+
+            let mut wrapped_cmd = assert_cmd::Command::from(cmd.cmd);
+            wrapped_cmd.write_stdin(stdin_content);
+
+            let created_file = &cmd
+                .temp_dir
+                .child("main-vault/markdown-frontmatter-note.md");
+
+            let _ = &wrapped_cmd.assert().success();
+
+            created_file.assert(predicate::str::diff(stdin_content));
+        }
+
+        #[test]
+        fn json_stdin_properties_and_body() {
+            let json_content = r##"{"properties": {"title": "Test Note"}, "body": "# Heading\n\nSome content\n"}"##;
+            let cmd = Obx::from_command("notes create json-body-note.md");
+
+            let mut wrapped_cmd = assert_cmd::Command::from(cmd.cmd);
+            wrapped_cmd.write_stdin(json_content);
+
+            let created_file = &cmd.temp_dir.child("main-vault/json-body-note.md");
+
+            let _ = &wrapped_cmd.assert().success();
+
+            let file_content = indoc! {"
+                ---
+                title: Test Note
+                ---
+                # Heading
+
+                Some content
+            "};
+
+            created_file.assert(predicate::str::diff(file_content));
+        }
+
         #[test]
         fn allows_specifying_vault() {
             Obx::from_command("notes create created-in-another-vault --vault=secondary")
+                .with_editor(r#"echo "This was appended by \$EDITOR" >> "$1""#)
                 .assert_created("another/path/created-in-another-vault.md");
         }
 
         #[test]
-        #[ignore = "not implemented"]
         fn fails_if_note_exists() {
-            Obx::from_command("notes create simple-note.md")
-                .assert_stderr("The note simple-node.md already exists");
+            let cmd = Obx::from_command("notes create simple-note.md");
+            let existing_note = cmd.temp_dir.child("main-vault/simple-note.md");
+
+            let _ = &cmd.assert_stderr("The note simple-note.md already exists\n");
+            existing_note.assert(predicate::str::contains(
+                "This is the contents of simple-note.md",
+            ));
         }
 
         #[test]
@@ -181,6 +258,16 @@ mod notes {
             let _ = &cmd.assert_success();
             edit_file.assert(predicate::str::contains("This was appended by $EDITOR"));
         }
+
+        #[test]
+        fn discards_empty_note_left_unchanged() {
+            let cmd = Obx::from_command("notes create new-note.md");
+            let new_note = cmd.temp_dir.child("main-vault/new-note.md");
+            let expected_stdout = format!("Discarded empty note {}\n", new_note.display());
+
+            let _ = &cmd.assert_stdout(expected_stdout);
+            new_note.assert(predicate::path::missing());
+        }
     }
 
     mod edit {
@@ -197,19 +284,132 @@ mod notes {
             edit_file.assert(predicate::str::contains("This was appended by $EDITOR"));
         }
 
+        #[test]
+        fn prints_summary_of_changes() {
+            Obx::from_command("notes edit simple-note.md")
+                .with_editor(
+                    r#"perl -pi -e 's/contents/text/' "$1" && printf '\nNew line\n' >> "$1""#,
+                )
+                .assert_stdout("Saved changes to simple-note.md (+2 -1)\n");
+        }
+
+        #[test]
+        fn refreshes_updated_timestamp_when_enabled() {
+            let cmd = Obx::from_command("notes edit simple-note.md")
+                .with_editor(r#"printf '\nNew line\n' >> "$1""#)
+                .with_extra_config(indoc! {r#"
+                    timestamps:
+                      updated_key: modified
+                      format: "%Y-%m-%d"
+                      timezone: utc
+                "#});
+            let edit_file = cmd.temp_dir.child("main-vault/simple-note.md");
+            let today = chrono::Utc::now().format("%Y-%m-%d");
+
+            let _ = &cmd.assert_stdout("Saved changes to simple-note.md (+1 -0)\n");
+
+            edit_file.assert(predicate::str::starts_with(format!(
+                "---\nmodified: {today}\n---\n# Simple note\n"
+            )));
+        }
+
+        #[test]
+        fn prints_when_nothing_changed() {
+            Obx::from_command("notes edit simple-note.md")
+                .assert_stdout("No changes to simple-note.md\n");
+        }
+
+        #[test]
+        fn discards_new_empty_note_left_unchanged() {
+            let cmd = Obx::from_command("notes edit new-note.md --create");
+            let new_note = cmd.temp_dir.child("main-vault/new-note.md");
+
+            let _ = &cmd.assert_stdout("No changes, discarded empty note new-note.md\n");
+            new_note.assert(predicate::path::missing());
+        }
+
         #[test]
         fn prints_on_editor_missing() {
             let mut cmd = Obx::from_command("notes edit simple-note.md");
             cmd.cmd.env_remove("EDITOR");
 
-            cmd.assert_stderr("$EDITOR not found\n");
+            cmd.assert_error(6, "$EDITOR not found\n");
         }
 
         #[test]
         fn prints_on_editor_fail() {
             let cmd = Obx::from_command("notes edit simple-note.md").with_editor(r"exit 1");
 
-            cmd.assert_stderr("Editor exited with non-0 exit code\n");
+            cmd.assert_error(6, "Editor exited with non-0 exit code\n");
+        }
+
+        /// Use a mock editor with a specific program name, so we can check the jump
+        /// syntax for it. The editor records the arguments it was called with
+        fn with_named_editor(mut cmd: Obx, name: &str, editor_args: &str) -> Obx {
+            let script = create_editor_script(
+                r#"echo "$@" > "$(dirname "$0")/editor-args.txt""#,
+                &cmd.temp_dir,
+            );
+            let named_editor = cmd.temp_dir.child(name);
+            std::fs::rename(script.path(), named_editor.path()).unwrap();
+
+            let editor = format!("{} {editor_args}", named_editor.display());
+            cmd.env("EDITOR", editor.trim());
+            cmd
+        }
+
+        #[test]
+        fn opens_vim_at_line() {
+            let cmd = with_named_editor(Obx::from_command("notes edit simple-note:3"), "vim", "");
+            let note_path = cmd.temp_dir.child("main-vault/simple-note.md");
+            let editor_args = cmd.temp_dir.child("editor-args.txt");
+
+            let _ = &cmd.assert_stdout("No changes to simple-note.md\n");
+            editor_args.assert(predicate::str::diff(format!(
+                "+3 {}\n",
+                note_path.display()
+            )));
+        }
+
+        #[test]
+        fn opens_vscode_at_heading_with_editor_arguments() {
+            let mut cmd = with_named_editor(Obx::from_command("notes edit"), "code", "--wait");
+            cmd.cmd.arg("complex-note#Rich note");
+            let note_path = cmd.temp_dir.child("main-vault/complex-note.md");
+            let editor_args = cmd.temp_dir.child("editor-args.txt");
+
+            let _ = &cmd.assert_success();
+            editor_args.assert(predicate::str::diff(format!(
+                "--wait -g {}:5\n",
+                note_path.display()
+            )));
+        }
+
+        #[test]
+        fn opens_at_block_with_configured_pattern() {
+            let cmd = Obx::from_command("notes edit hub-note#^hub-links")
+                .with_editor(r#"echo "$@" > "$(dirname "$0")/editor-args.txt""#)
+                .with_extra_config(r#"editor_line_pattern: "--line={line} {file}""#);
+            let note_path = cmd.temp_dir.child("main-vault/hub-note.md");
+            let editor_args = cmd.temp_dir.child("editor-args.txt");
+
+            let _ = &cmd.assert_success();
+            editor_args.assert(predicate::str::diff(format!(
+                "--line=10 {}\n",
+                note_path.display()
+            )));
+        }
+
+        #[test]
+        fn fails_for_missing_heading() {
+            Obx::from_command("notes edit complex-note#Missing")
+                .assert_stderr("Could not find heading `Missing` in complex-note.md\n");
+        }
+
+        #[test]
+        fn fails_for_lines_past_the_end() {
+            Obx::from_command("notes edit simple-note:9999")
+                .assert_stderr("Invalid line 9999 in simple-note.md\n");
         }
 
         // These tests jump through some extra hoops to simulate a tty in order
@@ -286,7 +486,7 @@ mod notes {
             let expected_path = cmd.temp_dir.child("main-vault/simple-note.md");
             let expected_uri = format!(
                 "obsidian://open?vault=main&file={}\n",
-                expected_path.display()
+                expected_path.display().to_string().replace('/', "%2F")
             );
             cmd.assert_stdout(expected_uri);
         }
@@ -297,7 +497,7 @@ mod notes {
             let expected_path = cmd.temp_dir.child("another/path/from-another-vault.md");
             let expected_uri = format!(
                 "obsidian://open?vault=secondary&file={}\n",
-                expected_path.display()
+                expected_path.display().to_string().replace('/', "%2F")
             );
             cmd.assert_stdout(expected_uri);
         }
@@ -321,6 +521,64 @@ mod notes {
 
             cmd.assert_stdout(format!("{}\n", expected_path.display()));
         }
+
+        #[test]
+        fn resolves_notes_by_basename() {
+            let cmd = Obx::from_command("notes path Child-Note");
+            let expected_path = cmd.temp_dir.child("main-vault/folder/child-note.md");
+
+            cmd.assert_stdout(format!("{}\n", expected_path.display()));
+        }
+
+        #[test]
+        fn resolves_notes_by_alias() {
+            let mut cmd = Obx::from_command("notes path --vault=secondary");
+            cmd.cmd.arg("daily sync");
+            let expected_path = cmd.temp_dir.child("another/path/standup.md");
+
+            cmd.assert_stdout(format!("{}\n", expected_path.display()));
+        }
+
+        #[test]
+        fn resolves_aliases_regardless_of_unicode_form() {
+            // A decomposed "é", as typed on some keyboards, matching a composed one
+            let cmd = Obx::from_command("notes path re\u{301}union --vault=secondary");
+            let expected_path = cmd.temp_dir.child("another/path/standup.md");
+
+            cmd.assert_stdout(format!("{}\n", expected_path.display()));
+        }
+
+        #[test]
+        fn requires_note_when_not_interactive() {
+            Obx::from_command("notes path").assert_stderr(
+                "No note given, a note is required when not running interactively\n",
+            );
+        }
+
+        #[test]
+        fn picks_note_interactively_when_omitted() {
+            let cmd = Obx::from_command("notes path");
+            let expected_path = cmd.temp_dir.child("main-vault/folder/child-note.md");
+
+            let mut p = cmd.spawn_interactive(Some(5_000)).unwrap();
+
+            p.exp_string("Pick a note").unwrap();
+            p.send_line("child-note").unwrap();
+
+            p.exp_string(&expected_path.display().to_string()).unwrap();
+        }
+
+        #[test]
+        fn fails_when_ambiguous_and_not_interactive() {
+            Obx::from_command("notes path planning --vault=secondary").assert_error(
+                8,
+                indoc! {"
+                Note `planning` is ambiguous, it could be any of:
+                  deadline.md
+                  standup.md
+            "},
+            );
+        }
     }
 
     mod properties {
@@ -341,16 +599,45 @@ mod notes {
         }
 
         #[test]
-        #[ignore = "file meta properties not implemented"]
         fn prints_meta_properties() {
-            Obx::from_command("notes properties empty-note.md --include-meta").assert_stdout(
-                indoc! { r"
-                | property      | value                  |
-                |---------------|------------------------|
-                | path          | /path/to/empty-note.md |
-                | created-at    | xxxx                   |
-                " },
-            );
+            Obx::from_command("notes properties empty-note.md --include-meta")
+                .assert_stdout_contains("│ $relative_path │ empty-note.md")
+                .assert_stdout_contains("│ $size          │ 0")
+                .assert_stdout_contains("│ $words         │ 0")
+                .assert_stdout_contains("│ $tags          │");
+        }
+
+        #[test]
+        fn prints_meta_properties_as_json() {
+            let mut cmd = Obx::from_command("notes properties hub-note --include-meta -f json");
+            let note_path = cmd.temp_dir.child("main-vault/hub-note.md");
+
+            let output = cmd.cmd.output().unwrap();
+            let properties: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+            let meta = &properties["$meta"];
+
+            assert!(output.status.success());
+            assert_eq!(properties["tags"], json!(["hub"]));
+            assert_eq!(meta["path"], json!(note_path.display().to_string()));
+            assert_eq!(meta["relative_path"], json!("hub-note.md"));
+            assert_eq!(meta["size"], json!(188));
+            assert_eq!(meta["words"], json!(19));
+            assert_eq!(meta["characters"], json!(168));
+            assert_eq!(meta["headings"], json!(1));
+            assert_eq!(meta["links"], json!(4));
+            assert_eq!(meta["backlinks"], json!(0));
+            assert_eq!(meta["tags"], json!(["hub"]));
+            assert!(meta["modified"].is_string());
+        }
+
+        #[test]
+        fn counts_backlinks_from_other_notes() {
+            let mut cmd = Obx::from_command("notes properties simple-note --include-meta -f json");
+
+            let output = cmd.cmd.output().unwrap();
+            let properties: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+            assert_eq!(properties["$meta"]["backlinks"], json!(2));
         }
 
         #[test]
@@ -372,6 +659,83 @@ mod notes {
         }
     }
 
+    mod mentions {
+        use super::*;
+
+        #[test]
+        fn prints_unlinked_mentions_of_title_and_aliases() {
+            Obx::from_command("notes mentions standup --vault secondary").assert_stdout(indoc! {"
+                journal.md
+                     6 Ran the standup, then a quick daily sync with the team.
+            "});
+        }
+
+        #[test]
+        fn prints_mentions_as_json() {
+            let stdout_match = &json!([
+                {
+                    "file": "journal.md",
+                    "line": 6,
+                    "column": 9,
+                    "text": "standup",
+                    "context": "Ran the standup, then a quick daily sync with the team."
+                },
+                {
+                    "file": "journal.md",
+                    "line": 6,
+                    "column": 31,
+                    "text": "daily sync",
+                    "context": "Ran the standup, then a quick daily sync with the team."
+                }
+            ]);
+
+            Obx::from_command("notes mentions standup --vault secondary -f json")
+                .assert_stdout(format!("{stdout_match}\n"));
+        }
+
+        #[test]
+        fn prints_message_when_there_are_no_mentions() {
+            Obx::from_command("notes mentions table").assert_stdout("No unlinked mentions found\n");
+        }
+
+        #[test]
+        fn shows_diff_on_dry_run() {
+            let cmd =
+                Obx::from_command("notes mentions standup --vault secondary --link --dry-run");
+            let journal = cmd.temp_dir.child("another/path/journal.md");
+
+            let _ = &cmd.assert_stdout(indoc! {"
+                journal.md:6
+                - Ran the standup, then a quick daily sync with the team.
+                + Ran the [[standup]], then a quick [[standup|daily sync]] with the team.
+            "});
+
+            journal.assert(predicate::str::contains("Ran the standup,"));
+        }
+
+        #[test]
+        fn links_mentions() {
+            let cmd = Obx::from_command("notes mentions standup --vault secondary --link --yes");
+            let journal = cmd.temp_dir.child("another/path/journal.md");
+
+            let _ = &cmd.assert_stdout("Linked 2 mentions in 1 notes\n");
+
+            journal.assert(predicate::str::contains(
+                "Ran the [[standup]], then a quick [[standup|daily sync]] with the team.\n",
+            ));
+        }
+
+        #[test]
+        fn aborts_without_confirmation() {
+            let cmd = Obx::from_command("notes mentions standup --vault secondary --link");
+            let journal = cmd.temp_dir.child("another/path/journal.md");
+
+            let _ = &cmd.assert_error(7, "Aborted\n");
+
+            journal.assert(predicate::str::contains("Ran the standup,"));
+        }
+    }
+
     mod backlinks {
         use super::*;
 
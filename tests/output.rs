@@ -0,0 +1,142 @@
+use indoc::indoc;
+mod utils;
+use predicates::prelude::*;
+use serde_json::json;
+use utils::*;
+
+mod output {
+    use super::*;
+
+    const ONE_VAULT: &str = indoc! {r"
+        current_vault: some-vault
+        vaults:
+            - name: some-vault
+              path: /some/path
+    "};
+
+    #[test]
+    fn prints_lists_as_csv() {
+        Obx::from_command("vaults list --format csv")
+            .with_config_file(ONE_VAULT)
+            .assert_stdout("name,path\nsome-vault,/some/path\n");
+    }
+
+    #[test]
+    fn prints_lists_as_tsv() {
+        Obx::from_command("vaults list --format tsv")
+            .with_config_file(ONE_VAULT)
+            .assert_stdout("name\tpath\nsome-vault\t/some/path\n");
+    }
+
+    #[test]
+    fn prints_lists_as_markdown() {
+        Obx::from_command("vaults list --format markdown")
+            .with_config_file(ONE_VAULT)
+            .assert_stdout(indoc! {"
+                | name | path |
+                | --- | --- |
+                | some-vault | /some/path |
+            "});
+    }
+
+    #[test]
+    fn prints_lists_as_ndjson() {
+        Obx::from_command("graph orphans --format ndjson --vault secondary")
+            .assert_stdout("\"deadline\"\n\"from-another-vault\"\n");
+    }
+
+    #[test]
+    fn prints_yaml() {
+        Obx::from_command("graph stats --format yaml --vault secondary").assert_stdout(indoc! {"
+            notes: 4
+            links: 1
+            orphans: 2
+            dead_ends: 1
+            components: 3
+            largest_component: 2
+        "});
+    }
+
+    #[test]
+    fn prints_messages_as_json() {
+        Obx::from_command("vaults switch secondary --format json")
+            .assert_stdout("{\"message\":\"Switched to vault secondary\"}\n");
+    }
+
+    #[test]
+    fn prints_paths_as_json() {
+        let cmd = Obx::from_command("notes path simple-note --format json");
+        let stdout_match = &json!({
+            "path": format!("{}/main-vault/simple-note.md", cmd.temp_dir.display())
+        });
+
+        cmd.assert_stdout(format!("{stdout_match}\n"));
+    }
+
+    #[test]
+    fn prints_note_contents_as_json() {
+        Obx::from_command("notes view empty-note --format json")
+            .assert_stdout_contains(r#""content":""}"#);
+    }
+
+    #[test]
+    fn accepts_the_format_before_the_command() {
+        Obx::from_command("--format json graph dead-ends --vault secondary")
+            .assert_stdout("[\"standup\"]\n");
+    }
+
+    #[test]
+    fn prints_config_as_json() {
+        Obx::from_command("config print --format json")
+            .assert_stdout_contains(r#"{"version":1,"current_vault":"main""#);
+    }
+
+    #[test]
+    fn leaves_exported_files_as_they_are() {
+        Obx::from_command("tasks ics --format json --vault secondary")
+            .assert_stdout_contains("BEGIN:VCALENDAR\r\n");
+    }
+
+    #[test]
+    fn rejects_graph_formats_outside_graph_export() {
+        let mut cmd = Obx::from_command("vaults switch secondary --format dot");
+
+        cmd.cmd.assert().code(2).stderr(predicate::str::contains(
+            "--format dot and --format graphml can only be used with `graph export`",
+        ));
+    }
+
+    #[test]
+    fn prints_errors_as_json() {
+        let stderr_match = json!({
+            "error": {
+                "kind": "note_not_found",
+                "message": "Could not find note `does-not-exist.md`",
+                "exit_code": 3,
+            }
+        });
+
+        Obx::from_command("notes view does-not-exist.md --format json")
+            .assert_error(3, format!("{stderr_match}\n"));
+    }
+
+    #[test]
+    fn prints_general_errors_as_json() {
+        let stderr_match = json!({
+            "error": {
+                "kind": "error",
+                "message": "Unknown config key `nope`",
+                "exit_code": 1,
+            }
+        });
+
+        Obx::from_command("config get nope --format json")
+            .assert_error(1, format!("{stderr_match}\n"));
+    }
+
+    #[test]
+    fn prints_errors_as_text_in_other_formats() {
+        Obx::from_command("graph path simple-note does-not-exist --format csv")
+            .assert_error(3, "Could not find note `does-not-exist`\n");
+    }
+}
@@ -0,0 +1,380 @@
+use assert_fs::prelude::*;
+use indoc::indoc;
+mod utils;
+use predicates::prelude::*;
+use serde_json::json;
+use utils::*;
+
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+mod tasks {
+    use super::*;
+
+    mod list {
+        use super::*;
+
+        #[test]
+        fn prints_tasks_grouped_by_note() {
+            Obx::from_command("tasks list").assert_stdout(indoc! {"
+                folder/project-tasks.md
+                     3 [ ] Plan the sprint [due:: 2024-02-01] #planning
+                     4 [?] Decide on a name
+
+                tasks.md
+                     6 [ ] Write the report 📅 2024-01-10
+                     7 [x] Send the invoice ✅ 2024-01-02
+                     8 [/] Review the draft #review ⏳ 2024-01-05
+                     9 [-] Cancelled idea
+                    10 [ ] Water the plants 🔁 every week 📅 2024-01-08
+            "});
+        }
+
+        #[test]
+        fn filters_by_status() {
+            Obx::from_command("tasks list --status open --status ?").assert_stdout(indoc! {"
+                folder/project-tasks.md
+                     3 [ ] Plan the sprint [due:: 2024-02-01] #planning
+                     4 [?] Decide on a name
+
+                tasks.md
+                     6 [ ] Write the report 📅 2024-01-10
+                     8 [/] Review the draft #review ⏳ 2024-01-05
+                    10 [ ] Water the plants 🔁 every week 📅 2024-01-08
+            "});
+        }
+
+        #[test]
+        fn filters_by_task_and_note_tags() {
+            Obx::from_command("tasks list --tag planning").assert_stdout(indoc! {"
+                folder/project-tasks.md
+                     3 [ ] Plan the sprint [due:: 2024-02-01] #planning
+            "});
+
+            Obx::from_command("tasks list --tag work --status done").assert_stdout(indoc! {"
+                tasks.md
+                     7 [x] Send the invoice ✅ 2024-01-02
+            "});
+        }
+
+        #[test]
+        fn filters_by_folder() {
+            Obx::from_command("tasks list --folder folder").assert_stdout(indoc! {"
+                folder/project-tasks.md
+                     3 [ ] Plan the sprint [due:: 2024-02-01] #planning
+                     4 [?] Decide on a name
+            "});
+        }
+
+        #[test]
+        fn filters_by_dates() {
+            Obx::from_command("tasks list --due-before 2024-01-09").assert_stdout(indoc! {"
+                tasks.md
+                    10 [ ] Water the plants 🔁 every week 📅 2024-01-08
+            "});
+
+            Obx::from_command("tasks list --scheduled-after 2024-01-01").assert_stdout(indoc! {"
+                tasks.md
+                     8 [/] Review the draft #review ⏳ 2024-01-05
+            "});
+        }
+
+        #[test]
+        fn prints_message_when_empty() {
+            Obx::from_command("tasks list --tag does-not-exist").assert_stdout("No tasks found\n");
+        }
+
+        #[test]
+        fn prints_tasks_as_json() {
+            let stdout_match = &json!([{
+                "id": "tasks.md:7",
+                "file": "tasks.md",
+                "line": 7,
+                "status": "done",
+                "symbol": "x",
+                "description": "Send the invoice ✅ 2024-01-02",
+                "tags": [],
+                "done": "2024-01-02"
+            }]);
+
+            Obx::from_command("tasks list --status done -f json")
+                .assert_stdout(format!("{stdout_match}\n"));
+        }
+
+        #[test]
+        fn skips_notes_that_cannot_be_read() {
+            let mut obx = Obx::from_command("tasks list --folder folder");
+            obx.temp_dir
+                .child("main-vault/folder/binary.md")
+                .write_binary(&[0xff, 0xfe, 0x00])
+                .unwrap();
+
+            obx.cmd
+                .assert()
+                .success()
+                .stdout(predicate::str::contains("Plan the sprint"))
+                .stderr(
+                    predicate::str::contains("binary.md").and(predicate::str::contains(
+                        "stream did not contain valid UTF-8, skipping it",
+                    )),
+                );
+        }
+    }
+
+    mod done {
+        use super::*;
+
+        #[test]
+        fn completes_task() {
+            let cmd = Obx::from_command("tasks done tasks.md:6");
+            let note = cmd.temp_dir.child("main-vault/tasks.md");
+
+            let _ = &cmd.assert_stdout("Completed task tasks.md:6\n");
+
+            note.assert(predicate::str::contains(format!(
+                "- [x] Write the report 📅 2024-01-10 ✅ {}\n",
+                today()
+            )));
+        }
+
+        #[test]
+        fn reopens_done_task() {
+            let cmd = Obx::from_command("tasks done tasks:7");
+            let note = cmd.temp_dir.child("main-vault/tasks.md");
+
+            let _ = &cmd.assert_stdout("Reopened task tasks.md:7\n");
+
+            note.assert(predicate::str::contains("- [ ] Send the invoice\n"));
+        }
+
+        #[test]
+        fn inserts_next_occurrence_of_recurring_task() {
+            let cmd = Obx::from_command("tasks done tasks.md:10");
+            let note = cmd.temp_dir.child("main-vault/tasks.md");
+
+            let _ = &cmd.assert_stdout(
+                "Completed task tasks.md:11, next occurrence added at tasks.md:10\n",
+            );
+
+            note.assert(predicate::str::contains(format!(
+                indoc! {"
+                    - [ ] Water the plants 🔁 every week 📅 2024-01-15
+                    - [x] Water the plants 🔁 every week 📅 2024-01-08 ✅ {}
+                "},
+                today()
+            )));
+        }
+
+        #[test]
+        fn follows_the_task_when_timestamps_shorten_the_frontmatter() {
+            let cmd = Obx::from_command("tasks done tidy.md:6").with_extra_config(indoc! {r#"
+                timestamps:
+                  format: "%Y-%m-%d"
+                  timezone: utc
+            "#});
+            let note = cmd.temp_dir.child("main-vault/tidy.md");
+            note.write_str("---\nupdated:\n  - a\n  - b\n---\n- [ ] Tidy up\n")
+                .unwrap();
+            let today = chrono::Utc::now().format("%Y-%m-%d");
+
+            let _ = &cmd.assert_stdout("Completed task tidy.md:4\n");
+
+            note.assert(predicate::str::starts_with(format!(
+                "---\nupdated: {today}\n---\n- [x] Tidy up"
+            )));
+        }
+
+        #[test]
+        fn fails_for_lines_that_are_not_tasks() {
+            Obx::from_command("tasks done tasks.md:4")
+                .assert_stderr("Could not toggle task tasks.md:4, line is not a task\n");
+        }
+
+        #[test]
+        fn fails_for_invalid_ids() {
+            Obx::from_command("tasks done tasks.md")
+                .assert_stderr("Invalid task `tasks.md`, expected <note>:<line>\n");
+        }
+    }
+
+    mod add {
+        use super::*;
+
+        #[test]
+        fn appends_task_to_note() {
+            let cmd = Obx::from_command("tasks add simple-note Buy-milk --due 2024-03-01");
+            let note = cmd.temp_dir.child("main-vault/simple-note.md");
+
+            let _ = &cmd.assert_stdout("Added task simple-note.md:4\n");
+
+            note.assert(predicate::str::diff(indoc! {"
+                # Simple note
+
+                This is the contents of simple-note.md
+                - [ ] Buy-milk 📅 2024-03-01
+            "}));
+        }
+
+        #[test]
+        fn creates_missing_note() {
+            let cmd = Obx::from_command("tasks add inbox/capture Call-back --scheduled 2024-03-01");
+            let note = cmd.temp_dir.child("main-vault/inbox/capture.md");
+
+            let _ = &cmd.assert_stdout("Added task inbox/capture.md:1\n");
+
+            note.assert(predicate::str::diff("- [ ] Call-back ⏳ 2024-03-01\n"));
+        }
+
+        #[test]
+        fn adds_timestamps_by_default() {
+            let cmd = Obx::from_command("tasks add inbox/capture Call-back");
+            let config = default_config(&cmd.temp_dir).replace("timestamps: false\n", "");
+            let cmd = cmd.with_config_file(&config);
+            let note = cmd.temp_dir.child("main-vault/inbox/capture.md");
+
+            let _ = &cmd.assert_stdout("Added task inbox/capture.md:5\n");
+
+            note.assert(
+                predicate::str::starts_with("---\ncreated: ")
+                    .and(predicate::str::ends_with("---\n- [ ] Call-back\n")),
+            );
+        }
+
+        #[test]
+        fn adds_timestamps_to_new_notes_when_enabled() {
+            let cmd = Obx::from_command("tasks add inbox/capture Call-back").with_extra_config(
+                indoc! {r#"
+                    timestamps:
+                      format: "%Y-%m-%d"
+                      timezone: utc
+                "#},
+            );
+            let note = cmd.temp_dir.child("main-vault/inbox/capture.md");
+            let today = chrono::Utc::now().format("%Y-%m-%d");
+
+            let _ = &cmd.assert_stdout("Added task inbox/capture.md:5\n");
+
+            note.assert(predicate::str::diff(format!(
+                "---\ncreated: {today}\nupdated: {today}\n---\n- [ ] Call-back\n"
+            )));
+        }
+
+        #[test]
+        fn skips_timestamps_for_vaults_that_opt_out() {
+            let cmd = Obx::from_command("tasks add from-another-vault Call-back --vault secondary");
+            let config = format!(
+                indoc! {r#"
+                    current_vault: main
+                    vaults:
+                    - name: secondary
+                      path: {dir}/another/path
+                      timestamps: false
+                    timestamps:
+                      enabled: true
+                "#},
+                dir = cmd.temp_dir.display()
+            );
+            let cmd = cmd.with_config_file(&config);
+            let note = cmd.temp_dir.child("another/path/from-another-vault.md");
+
+            let _ = &cmd.assert_stdout("Added task from-another-vault.md:2\n");
+
+            note.assert(predicate::str::diff(indoc! {"
+                This note is from the secondary vault
+                - [ ] Call-back
+            "}));
+        }
+    }
+
+    mod ics {
+        use super::*;
+
+        #[test]
+        fn prints_dated_tasks_as_vtodos() {
+            let mut cmd = Obx::from_command("tasks ics");
+            let note_path = cmd.temp_dir.child("main-vault/tasks.md");
+
+            let expected_url = format!(
+                "URL:obsidian://open?vault=main&file={}",
+                note_path.display().to_string().replace('/', "%2F")
+            );
+
+            let output = cmd.cmd.output().unwrap();
+            let stdout = String::from_utf8(output.stdout).unwrap();
+
+            assert!(output.status.success());
+            assert!(stdout.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+            assert!(stdout.ends_with("END:VCALENDAR\n"));
+
+            // Only tasks with a due, scheduled or start date are exported
+            assert_eq!(stdout.matches("BEGIN:VTODO").count(), 4);
+            assert!(stdout.contains("SUMMARY:Write the report\r\n"));
+            assert!(stdout.contains("DUE;VALUE=DATE:20240110\r\n"));
+            assert!(stdout.contains("DTSTART;VALUE=DATE:20240105\r\n"));
+            assert!(stdout.contains("STATUS:IN-PROCESS\r\n"));
+            assert!(stdout.replace("\r\n ", "").contains(&expected_url));
+        }
+
+        #[test]
+        fn uses_note_due_property_for_undated_tasks() {
+            let cmd = Obx::from_command("tasks ics --vault secondary --output tasks.ics");
+            let ics_file = cmd.temp_dir.child("tasks.ics");
+
+            let _ = &cmd.assert_stdout("Wrote 2 tasks to tasks.ics\n");
+
+            ics_file.assert(
+                predicate::str::contains(
+                    "SUMMARY:Inherit the deadline of the note\r\nDESCRIPTION:deadline.md:5",
+                )
+                .and(predicate::str::contains("DUE;VALUE=DATE:20240301\r\n"))
+                .and(predicate::str::contains(
+                    "SUMMARY:Keep my own date\\, with a comma\r\n",
+                ))
+                .and(predicate::str::contains("DTSTART;VALUE=DATE:20240220\r\n")),
+            );
+        }
+
+        #[test]
+        fn encodes_note_urls() {
+            let mut cmd = Obx::from_command("tasks ics");
+            let note = cmd.temp_dir.child("main-vault/weekly review.md");
+            note.write_str("- [ ] Plan the week 📅 2024-03-04\n")
+                .unwrap();
+
+            let output = cmd.cmd.output().unwrap();
+            let stdout = String::from_utf8(output.stdout)
+                .unwrap()
+                .replace("\r\n ", "");
+
+            assert!(output.status.success());
+            assert!(stdout.contains(&format!(
+                "URL:obsidian://open?vault=main&file={}\r\n",
+                note.display()
+                    .to_string()
+                    .replace('/', "%2F")
+                    .replace(' ', "%20")
+            )));
+        }
+
+        #[test]
+        fn uids_are_stable_between_exports() {
+            let uids = |cmd: Obx| {
+                let mut cmd = cmd;
+                let output = cmd.cmd.output().unwrap();
+                String::from_utf8(output.stdout)
+                    .unwrap()
+                    .lines()
+                    .filter(|line| line.starts_with("UID:"))
+                    .map(str::to_string)
+                    .collect::<Vec<String>>()
+            };
+
+            let first = uids(Obx::from_command("tasks ics"));
+            let second = uids(Obx::from_command("tasks ics --component event"));
+
+            assert_eq!(first.len(), 4);
+            assert_eq!(first, second);
+        }
+    }
+}
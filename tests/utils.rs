@@ -28,18 +28,7 @@ impl Obx {
         }
 
         let config_path = temp_dir.child("./config/obx/");
-
-        let initial_cfg_file = format!(
-            indoc! {
-            r#"current_vault: main
-                vaults:
-                - name: main
-                  path: {dir}/main-vault/
-                - name: secondary
-                  path: {dir}/another/path
-                "#},
-            dir = temp_dir.display()
-        );
+        let initial_cfg_file = default_config(&temp_dir);
 
         cmd.env("OBX_CONFIG_DIR", config_path.display().to_string());
 
@@ -75,6 +64,21 @@ impl Obx {
         self
     }
 
+    /// Add extra settings to the end of the default config file. Any `timestamps`
+    /// settings replace the default config's `timestamps: false`
+    pub fn with_extra_config(self, extra_cfg: &str) -> Self {
+        let mut cfg_file = default_config(&self.temp_dir);
+        if extra_cfg
+            .lines()
+            .any(|line| line.starts_with("timestamps:"))
+        {
+            cfg_file = cfg_file.replace("timestamps: false\n", "");
+        }
+
+        let cfg_file = format!("{cfg_file}{extra_cfg}");
+        self.with_config_file(&cfg_file)
+    }
+
     pub fn with_editor<S>(mut self, editor_script: S) -> Self
     where
         S: Into<String>,
@@ -157,6 +161,36 @@ impl Obx {
             .stderr(predicate::str::diff(stderr_match.into()));
         self
     }
+
+    /// Like `assert_stderr`, for errors that exit with their own code
+    pub fn assert_error<S>(mut self, code: i32, stderr_match: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.cmd
+            .assert()
+            .code(code)
+            .stderr(predicate::str::diff(stderr_match.into()));
+        self
+    }
+}
+
+/// The config file every test starts with, with a `main` and `secondary` vault.
+/// Timestamps are turned off so notes can be compared exactly
+pub fn default_config(temp_dir: &TempDir) -> String {
+    format!(
+        indoc! {
+        r#"version: 1
+            current_vault: main
+            vaults:
+            - name: main
+              path: {dir}/main-vault/
+            - name: secondary
+              path: {dir}/another/path
+            timestamps: false
+            "#},
+        dir = temp_dir.display()
+    )
 }
 
 /// Create a `TempDir` and clone our example vault into it
@@ -9,7 +9,8 @@ mod vaults {
 
     mod create {
         use super::*;
-        use assert_fs::prelude::FileTouch;
+        use assert_fs::prelude::*;
+        use predicates::prelude::*;
 
         #[test]
         fn defaults_to_folder_name() {
@@ -41,6 +42,82 @@ mod vaults {
             );
         }
 
+        #[test]
+        fn fails_on_duplicate_name() {
+            Obx::from_command("vaults create main-vault --name secondary").assert_stderr(
+                "Could not create vault `secondary`, a vault with that name already exists\n",
+            );
+        }
+
+        #[test]
+        fn init_scaffolds_a_new_vault() {
+            let cmd = Obx::from_command("vaults create path/to/fresh-vault --init");
+            let vault = cmd.temp_dir.child("path/to/fresh-vault");
+
+            let _ = &cmd.assert_stdout("Created vault fresh-vault\n");
+
+            vault.child(".obsidian/app.json").assert("{}");
+            vault.child(".obsidian/appearance.json").assert("{}");
+            vault
+                .child(".obsidian/core-plugins.json")
+                .assert(predicate::str::contains(r#""file-explorer""#));
+        }
+
+        #[test]
+        fn init_copies_starter_templates() {
+            let cmd = Obx::from_command("vaults create fresh-vault --init --templates starter");
+            let _ = cmd
+                .temp_dir
+                .child("starter/daily.md")
+                .write_str("# {{date}}\n");
+            let vault = cmd.temp_dir.child("fresh-vault");
+
+            let _ = &cmd.assert_success();
+
+            vault.child("templates/daily.md").assert("# {{date}}\n");
+            vault
+                .child(".obsidian/templates.json")
+                .assert(predicate::str::contains(r#""folder": "templates""#));
+        }
+
+        #[test]
+        fn init_uses_the_vaults_own_templates_folder() {
+            let cmd = Obx::from_command("vaults create fresh-vault --init --templates starter");
+            let _ = cmd
+                .temp_dir
+                .child("starter/daily.md")
+                .write_str("# {{date}}\n");
+            let vault = cmd.temp_dir.child("fresh-vault");
+            let _ = vault
+                .child(".obx.yml")
+                .write_str("templates_folder: meta/templates\n");
+
+            let _ = &cmd.assert_success();
+
+            vault
+                .child("meta/templates/daily.md")
+                .assert("# {{date}}\n");
+            vault
+                .child(".obsidian/templates.json")
+                .assert(predicate::str::contains(r#""folder": "meta/templates""#));
+        }
+
+        #[test]
+        fn registers_with_obsidian() {
+            let mut cmd = Obx::from_command("vaults create fresh-vault --init --register");
+            let settings = cmd.temp_dir.child("obsidian.json");
+            let _ = settings.write_str(r#"{"vaults":{},"updateDisabled":true}"#);
+            cmd.env("OBX_OBSIDIAN_SETTINGS", settings.display().to_string());
+
+            let _ = &cmd
+                .assert_stdout("Created vault fresh-vault\nRegistered fresh-vault with Obsidian\n");
+
+            settings.assert(
+                predicate::str::contains("fresh-vault\",\"ts\":")
+                    .and(predicate::str::contains(r#""updateDisabled":true"#)),
+            );
+        }
+
         #[test]
         fn persists_changes() {
             let create_cmd = Obx::from_command("vaults create path/to/new/vault");
@@ -126,7 +203,7 @@ mod vaults {
         #[test]
         fn fails_on_missing_vault() {
             Obx::from_command("vaults switch does-not-exist")
-                .assert_stderr("Could not switch to vault `does-not-exist`, vault doesn't exist\n");
+                .assert_error(4, "Could not find vault `does-not-exist` in config\n");
         }
 
         #[test]
@@ -160,4 +237,464 @@ mod vaults {
             ));
         }
     }
+
+    mod remove {
+        use super::*;
+        use assert_fs::prelude::*;
+        use predicates::prelude::*;
+
+        #[test]
+        fn removes_vault() {
+            let cmd = Obx::from_command("vaults remove secondary --yes");
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+
+            let _ = &cmd.assert_stdout("Removed vault secondary\n");
+
+            config_file.assert(predicate::str::contains("secondary").not());
+        }
+
+        #[test]
+        fn aborts_without_confirmation() {
+            let cmd = Obx::from_command("vaults remove secondary");
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+
+            let _ = &cmd.assert_error(7, "Aborted\n");
+
+            config_file.assert(predicate::str::contains("name: secondary"));
+        }
+
+        #[test]
+        fn refuses_to_remove_current_vault() {
+            Obx::from_command("vaults remove main --yes").assert_stderr(
+                "Could not remove vault `main`, it's the current vault. Switch to another vault first\n",
+            );
+        }
+
+        #[test]
+        fn fails_on_unknown_vault() {
+            Obx::from_command("vaults remove does-not-exist --yes")
+                .assert_stderr("Could not find vault `does-not-exist` in config\n");
+        }
+    }
+
+    mod rename {
+        use super::*;
+        use assert_fs::prelude::*;
+        use predicates::prelude::*;
+
+        #[test]
+        fn renames_vault() {
+            let cmd = Obx::from_command("vaults rename secondary archive");
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+
+            let _ = &cmd.assert_stdout("Renamed vault secondary to archive\n");
+
+            config_file.assert(
+                predicate::str::contains("name: archive")
+                    .and(predicate::str::contains("current_vault: main\n")),
+            );
+        }
+
+        #[test]
+        fn updates_current_vault() {
+            let cmd = Obx::from_command("vaults rename main notes");
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+
+            let _ = &cmd.assert_stdout("Renamed vault main to notes\n");
+
+            config_file.assert(predicate::str::contains("current_vault: notes\n"));
+        }
+
+        #[test]
+        fn fails_on_existing_name() {
+            Obx::from_command("vaults rename main secondary").assert_stderr(
+                "Could not rename vault `main`, a vault named `secondary` already exists\n",
+            );
+        }
+    }
+
+    mod set_path {
+        use super::*;
+        use assert_fs::prelude::*;
+
+        #[test]
+        fn updates_vault_path() {
+            let cmd = Obx::from_command("vaults set-path secondary moved/vault");
+            let _ = cmd.temp_dir.child("moved/vault/file.md").touch();
+
+            let mut path_cmd = Obx::from_command("vaults path --vault secondary");
+            let tmp_config_path = cmd.temp_dir.child("./config/obx/");
+            path_cmd.env("OBX_CONFIG_DIR", tmp_config_path.display().to_string());
+
+            let _ = &cmd.assert_stdout_contains("Moved vault secondary to ");
+
+            path_cmd.assert_stdout_contains("moved/vault\n");
+        }
+
+        #[test]
+        fn fails_on_missing_dir() {
+            Obx::from_command("vaults set-path secondary /does/not/exist").assert_stderr(
+                "Could not move vault `secondary` at path `/does/not/exist`, directory not found\n",
+            );
+        }
+    }
+
+    mod sync {
+        use super::*;
+        use assert_fs::prelude::*;
+        use predicates::prelude::*;
+
+        /// Register the main vault and a new one in a mock `obsidian.json`
+        fn with_obsidian_vaults(mut cmd: Obx) -> Obx {
+            let dir = cmd.temp_dir.display().to_string();
+            let _ = cmd.temp_dir.child("new-vault/note.md").touch();
+
+            let settings = cmd.temp_dir.child("obsidian.json");
+            let vaults = json!({"vaults": {
+                "a1b2c3": {"path": format!("{dir}/main-vault"), "ts": 2000},
+                "d4e5f6": {"path": format!("{dir}/new-vault"), "ts": 1000, "open": true},
+            }});
+            settings.write_str(&vaults.to_string()).unwrap();
+
+            cmd.env("OBX_OBSIDIAN_SETTINGS", settings.display().to_string());
+            cmd
+        }
+
+        #[test]
+        fn adds_new_vaults_and_flags_missing_ones() {
+            let cmd = with_obsidian_vaults(Obx::from_command("vaults sync"));
+            let dir = cmd.temp_dir.display().to_string();
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+            let _ = std::fs::remove_dir_all(cmd.temp_dir.child("another"));
+
+            let _ = &cmd.assert_stdout(format!(
+                "Added vault new-vault at path `{dir}/new-vault`\n\
+                 Vault secondary is missing, `{dir}/another/path` doesn't exist. Use `obx vaults set-path` or `obx vaults remove` to fix it\n"
+            ));
+
+            config_file.assert(
+                predicate::str::contains("name: new-vault")
+                    .and(predicate::str::contains("current_vault: main\n")),
+            );
+        }
+
+        #[test]
+        fn keeps_custom_names() {
+            let cmd = Obx::from_command("vaults sync");
+            let config = default_config(&cmd.temp_dir)
+                .replace("current_vault: main", "current_vault: notes")
+                .replace("name: main", "name: notes");
+            let cmd = with_obsidian_vaults(cmd.with_config_file(&config));
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+
+            let _ = &cmd.assert_stdout_contains("Added vault new-vault");
+
+            config_file.assert(predicate::str::contains("name: main-vault").not());
+        }
+
+        #[test]
+        fn switches_to_the_open_vault_when_current_is_missing() {
+            let cmd = Obx::from_command("vaults sync");
+            let config = default_config(&cmd.temp_dir)
+                .replace("current_vault: main", "current_vault: secondary");
+            let cmd = with_obsidian_vaults(cmd.with_config_file(&config));
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+            let _ = std::fs::remove_dir_all(cmd.temp_dir.child("another"));
+
+            let _ =
+                &cmd.assert_stdout_contains("Switched to vault new-vault, it's open in Obsidian\n");
+
+            config_file.assert(predicate::str::contains("current_vault: new-vault\n"));
+        }
+
+        #[test]
+        fn dry_run_leaves_config_untouched() {
+            let cmd = with_obsidian_vaults(Obx::from_command("vaults sync --dry-run"));
+            let config_file = cmd.temp_dir.child("config/obx/config.yml");
+            let original = default_config(&cmd.temp_dir);
+
+            let _ = &cmd.assert_stdout_contains("Would add vault new-vault at path ");
+
+            config_file.assert(predicate::str::diff(original));
+        }
+    }
+
+    mod stats {
+        use super::*;
+        use assert_fs::prelude::*;
+        use predicates::prelude::*;
+
+        #[test]
+        fn prints_stats_as_json() {
+            let cmd = Obx::from_command("vaults stats -f json");
+            let _ = cmd.temp_dir.child("main-vault/images/photo.PNG").touch();
+
+            cmd.assert_stdout_contains(
+                r#"{"vault":"main","notes":11,"attachments":{"png":1},"words":"#,
+            )
+            .assert_stdout_contains(r#""folders":{"/":9,"folder":2}"#)
+            .assert_stdout_contains(r#""links":5,"link_density":0.45,"orphans":6,"#)
+            .assert_stdout_contains(r#""modified":11}]}"#);
+        }
+
+        #[test]
+        fn prints_stats_tables() {
+            Obx::from_command("vaults stats")
+                .assert_stdout_contains("│ Links per note │ 0.45")
+                .assert_stdout_contains("│ Week     │ Created │ Modified │");
+        }
+
+        #[test]
+        fn prints_stats_for_all_vaults() {
+            Obx::from_command("vaults stats --all-vaults -f json --weeks 1")
+                .assert_stdout_contains(r#"[{"vault":"main","notes":11,"#)
+                .assert_stdout_contains(r#"{"vault":"secondary","notes":4,"#);
+        }
+
+        #[test]
+        fn skips_missing_vaults_with_all_vaults() {
+            let mut cmd = Obx::from_command("vaults stats --all-vaults -f json --weeks 1");
+            let _ = std::fs::remove_dir_all(cmd.temp_dir.child("another"));
+            let missing = cmd.temp_dir.child("another/path");
+
+            cmd.cmd
+                .assert()
+                .success()
+                .stdout(predicate::str::starts_with(r#"[{"vault":"main","#))
+                .stdout(predicate::str::contains("secondary").not())
+                .stderr(format!(
+                    "Warning: Skipping vault secondary, `{}` doesn't exist\n",
+                    missing.display()
+                ));
+        }
+
+        #[test]
+        fn rejects_vault_with_all_vaults() {
+            Obx::from_command("vaults stats --all-vaults --vault main")
+                .assert_stderr("Use either --vault or --all-vaults, not both\n");
+        }
+    }
+
+    mod doctor {
+        use super::*;
+        use assert_fs::prelude::*;
+        use predicates::prelude::*;
+
+        const NO_OBSIDIAN: &str =
+            "No `.obsidian` folder, this folder hasn't been opened as a vault in Obsidian";
+
+        #[test]
+        fn reports_warnings() {
+            Obx::from_command("vaults doctor").assert_stdout(format!(
+                "warning: [main] {NO_OBSIDIAN}\n\
+                 warning: [main] empty-note.md: Note is empty\n\
+                 warning: [secondary] {NO_OBSIDIAN}\n\
+                 Found 0 errors and 3 warnings in 2 vaults\n"
+            ));
+        }
+
+        #[test]
+        fn checks_a_single_vault() {
+            let cmd = Obx::from_command("vaults doctor --vault secondary");
+            let _ = cmd
+                .temp_dir
+                .child("another/path/.obsidian")
+                .create_dir_all();
+
+            cmd.assert_stdout("No problems found in 1 vault\n");
+        }
+
+        #[test]
+        fn fails_on_invalid_frontmatter() {
+            let mut cmd = Obx::from_command("vaults doctor");
+            let _ = cmd
+                .temp_dir
+                .child("main-vault/broken.md")
+                .write_str("---\ntitle: ok\n  bad: indent\n---\n");
+
+            cmd.cmd
+                .assert()
+                .failure()
+                .stdout(predicate::str::contains(
+                    "error: [main] broken.md:3: Invalid frontmatter, ",
+                ))
+                .stderr("Found 1 error and 3 warnings in 2 vaults\n");
+        }
+
+        #[test]
+        fn reports_invalid_vault_settings_and_checks_the_other_vaults() {
+            let mut cmd = Obx::from_command("vaults doctor");
+            let _ = cmd
+                .temp_dir
+                .child("main-vault/.obx.yml")
+                .write_str("ignore: [\n");
+
+            cmd.cmd
+                .assert()
+                .failure()
+                .stdout(
+                    predicate::str::contains("error: [main] .obx.yml: Invalid vault settings, ")
+                        .and(predicate::str::contains(format!(
+                            "warning: [secondary] {NO_OBSIDIAN}\n"
+                        ))),
+                )
+                .stderr("Found 1 error and 3 warnings in 2 vaults\n");
+        }
+
+        #[test]
+        fn prints_the_report_and_the_error_as_json() {
+            let mut cmd = Obx::from_command("vaults doctor --format json");
+            let _ = std::fs::remove_dir_all(cmd.temp_dir.child("another"));
+
+            cmd.cmd
+                .assert()
+                .code(1)
+                .stdout(
+                    predicate::str::starts_with("[{")
+                        .and(predicate::str::contains(r#""check":"path""#)),
+                )
+                .stderr(predicate::str::starts_with(r#"{"error":{"kind":"error","#));
+        }
+
+        #[test]
+        fn fails_on_missing_vaults() {
+            let mut cmd = Obx::from_command("vaults doctor");
+            let dir = cmd.temp_dir.display().to_string();
+            let _ = std::fs::remove_dir_all(cmd.temp_dir.child("another"));
+
+            cmd.cmd
+                .assert()
+                .failure()
+                .stdout(predicate::str::contains(format!(
+                    "error: [secondary] Vault path `{dir}/another/path` doesn't exist\n"
+                )));
+        }
+
+        #[test]
+        fn reports_ambiguous_and_conflicting_files() {
+            let cmd = Obx::from_command("vaults doctor -f json");
+            let _ = cmd.temp_dir.child("main-vault/archive/Hub-Note.md").touch();
+            let _ = cmd
+                .temp_dir
+                .child("main-vault/tasks.sync-conflict-20240101-123456.md")
+                .write_str("# Tasks\n");
+
+            cmd.assert_stdout_contains(
+                r#""check":"duplicate-name","path":null,"line":null,"message":"Links to `hub-note` are ambiguous, it matches archive/Hub-Note.md, hub-note.md"}"#,
+            )
+            .assert_stdout_contains(
+                r#""check":"sync-conflict","path":"tasks.sync-conflict-20240101-123456.md""#,
+            );
+        }
+    }
+
+    mod discovery {
+        use super::*;
+        use assert_fs::prelude::*;
+        use predicates::prelude::*;
+
+        #[test]
+        fn uses_registered_vault_containing_cwd() {
+            let mut cmd = Obx::from_command("vaults path");
+            let vault_dir = cmd.temp_dir.child("another/path");
+            cmd.cmd.current_dir(&vault_dir);
+
+            cmd.assert_stdout(format!("{}\n", vault_dir.display()));
+        }
+
+        #[test]
+        fn current_uses_registered_vault_containing_cwd() {
+            let mut cmd = Obx::from_command("vaults current");
+            let vault_dir = cmd.temp_dir.child("another/path");
+            cmd.cmd.current_dir(&vault_dir);
+
+            cmd.assert_stdout(format!(
+                "Current vault is `secondary` at path `{}`\n",
+                vault_dir.display()
+            ));
+        }
+
+        #[test]
+        fn vault_flag_beats_cwd() {
+            let mut cmd = Obx::from_command("vaults path --vault main");
+            let main_vault = format!("{}/main-vault/\n", cmd.temp_dir.display());
+            cmd.cmd.current_dir(cmd.temp_dir.child("another/path"));
+
+            cmd.assert_stdout(main_vault);
+        }
+
+        #[test]
+        fn uses_unregistered_vault_containing_cwd() {
+            let mut cmd = Obx::from_command("notes path loose-note");
+            let vault_dir = cmd.temp_dir.child("loose-vault");
+            vault_dir.child(".obsidian").create_dir_all().unwrap();
+            vault_dir
+                .child("loose-note.md")
+                .write_str("# Loose\n")
+                .unwrap();
+            vault_dir.child("sub").create_dir_all().unwrap();
+            cmd.cmd.current_dir(vault_dir.child("sub"));
+
+            cmd.assert_stdout(format!("{}\n", vault_dir.child("loose-note.md").display()));
+        }
+
+        #[test]
+        fn edits_notes_in_an_unregistered_vault_without_a_config() {
+            let mut cmd = Obx::from_command("notes edit loose-note")
+                .with_editor(r#"printf 'Edited\n' >> "$1""#);
+            let vault_dir = cmd.temp_dir.child("loose-vault");
+            vault_dir.child(".obsidian").create_dir_all().unwrap();
+            vault_dir
+                .child(".obx.yml")
+                .write_str("timestamps:\n  format: \"%Y\"\n")
+                .unwrap();
+            vault_dir
+                .child("loose-note.md")
+                .write_str("# Loose\n")
+                .unwrap();
+            std::fs::remove_file(cmd.temp_dir.child("config/obx/config.yml")).unwrap();
+            cmd.cmd.current_dir(&vault_dir);
+
+            let _ = &cmd.assert_success();
+
+            vault_dir.child("loose-note.md").assert(
+                predicate::str::starts_with("---\nupdated: ")
+                    .and(predicate::str::ends_with("# Loose\nEdited\n")),
+            );
+        }
+
+        #[test]
+        fn reports_an_invalid_config_in_an_unregistered_vault() {
+            let mut cmd = Obx::from_command("notes path loose-note")
+                .with_config_file("version: 1\ncurrent_vault: [\n");
+            let vault_dir = cmd.temp_dir.child("loose-vault");
+            vault_dir.child(".obsidian").create_dir_all().unwrap();
+            cmd.cmd.current_dir(&vault_dir);
+
+            cmd.cmd
+                .assert()
+                .code(5)
+                .stderr(predicate::str::starts_with("Invalid config file "));
+        }
+
+        #[test]
+        fn resolves_note_paths_relative_to_cwd() {
+            let mut cmd = Obx::from_command("notes path ../simple-note");
+            let note_path = format!("{}/main-vault/simple-note.md\n", cmd.temp_dir.display());
+            cmd.cmd.current_dir(cmd.temp_dir.child("main-vault/folder"));
+
+            cmd.assert_stdout(note_path);
+        }
+
+        #[test]
+        fn applies_local_vault_settings() {
+            let cmd = Obx::from_command("graph stats");
+            cmd.temp_dir
+                .child("main-vault/.obx.yml")
+                .write_str("output_format: json\n")
+                .unwrap();
+
+            cmd.assert_stdout_contains(r#"{"notes":"#);
+        }
+    }
 }